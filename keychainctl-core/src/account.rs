@@ -0,0 +1,38 @@
+use std::env;
+use std::process::Command;
+
+use crate::{Error, Result, strip_trailing_newlines};
+
+const WHOAMI_BIN: &str = "/usr/bin/whoami";
+const ID_BIN: &str = "/usr/bin/id";
+
+/// Returns `account` when it is non-blank, otherwise `$USER`, otherwise the
+/// output of `whoami`.
+pub fn resolve(account: Option<String>) -> Result<String> {
+    if let Some(account) = account.filter(|value| !value.trim().is_empty()) {
+        return Ok(account);
+    }
+    if let Ok(user) = env::var("USER")
+        && !user.trim().is_empty()
+    {
+        return Ok(user);
+    }
+    let output = Command::new(WHOAMI_BIN)
+        .output()
+        .map_err(|error| Error::io("failed to determine current user", error))?;
+    if !output.status.success() {
+        return Err(Error::UnknownAccount);
+    }
+    let user = String::from_utf8(output.stdout).map_err(|_| Error::InvalidUtf8 {
+        what: "current user".to_string(),
+    })?;
+    Ok(strip_trailing_newlines(user))
+}
+
+/// Whether keychainctl is running as root, such as under `sudo`.
+pub fn is_root() -> bool {
+    Command::new(ID_BIN)
+        .arg("-u")
+        .output()
+        .is_ok_and(|output| output.status.success() && output.stdout.trim_ascii() == b"0")
+}
@@ -0,0 +1,130 @@
+//! Append-only log of secret reads, writes, and deletes.
+//!
+//! Each line of `audit.log` in the config directory is
+//! `timestamp<TAB>operation<TAB>account<TAB>service<TAB>result<TAB>pid<TAB>exe`,
+//! where `pid` and `exe` identify the process that ran keychainctl (its
+//! parent), and text fields escape tabs, newlines, and backslashes like the
+//! registry does.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::registry::{escape, unescape};
+use crate::{Error, Result, config};
+
+const PS_BIN: &str = "/bin/ps";
+
+/// One logged operation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// When the operation ran, in RFC 3339 form.
+    pub timestamp: String,
+    /// `get`, `set`, or `delete`.
+    pub operation: String,
+    pub account: String,
+    pub service: String,
+    /// `ok`, `not-found`, or `failed`.
+    pub result: String,
+    /// The process that invoked keychainctl.
+    pub caller: Caller,
+}
+
+/// A process, identified by its pid and executable.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Caller {
+    pub pid: u32,
+    /// The executable path, when it could be determined.
+    pub exe: Option<String>,
+}
+
+impl Caller {
+    /// Describes the parent of the current process.
+    pub fn parent() -> Self {
+        let pid = std::os::unix::process::parent_id();
+        Self {
+            pid,
+            exe: executable(pid),
+        }
+    }
+}
+
+/// Reads `/proc/<pid>/exe` on Linux and asks `ps` elsewhere.
+fn executable(pid: u32) -> Option<String> {
+    if let Ok(path) = fs::read_link(format!("/proc/{}/exe", pid)) {
+        return Some(path.display().to_string());
+    }
+    let output = Command::new(PS_BIN)
+        .args(["-o", "comm=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !name.is_empty()).then_some(name)
+}
+
+pub fn path() -> Result<PathBuf> {
+    Ok(config::dir()?.join("audit.log"))
+}
+
+/// Appends `record` to the log, creating it readable only by its owner.
+pub fn append(record: &Record) -> Result<()> {
+    let path = path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| Error::io("failed to create audit log directory", error))?;
+    }
+    let line = format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+        record.timestamp,
+        record.operation,
+        escape(&record.account),
+        escape(&record.service),
+        record.result,
+        record.caller.pid,
+        escape(record.caller.exe.as_deref().unwrap_or_default()),
+    );
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|error| Error::io(format!("failed to write {}", path.display()), error))
+}
+
+/// Reads every record, oldest first, skipping malformed lines.
+pub fn read() -> Result<Vec<Record>> {
+    let path = path()?;
+    let data = match fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => {
+            return Err(Error::io(
+                format!("failed to read {}", path.display()),
+                error,
+            ));
+        }
+    };
+    Ok(data.lines().filter_map(parse).collect())
+}
+
+fn parse(line: &str) -> Option<Record> {
+    let mut fields = line.split('\t');
+    let timestamp = fields.next()?.to_string();
+    let operation = fields.next()?.to_string();
+    let account = unescape(fields.next()?);
+    let service = unescape(fields.next()?);
+    let result = fields.next()?.to_string();
+    let pid = fields.next()?.parse().ok()?;
+    let exe = fields.next().map(unescape).filter(|exe| !exe.is_empty());
+    Some(Record {
+        timestamp,
+        operation,
+        account,
+        service,
+        result,
+        caller: Caller { pid, exe },
+    })
+}
@@ -0,0 +1,2284 @@
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::chunk::Chunked;
+use crate::{Error, Result, config, date};
+
+const SECURITY_BIN: &str = "/usr/bin/security";
+#[cfg(target_os = "linux")]
+const SECRET_TOOL_BIN: &str = "secret-tool";
+const AGE_BIN: &str = "age";
+const AGE_KEYGEN_BIN: &str = "age-keygen";
+const FILE_NAME: &str = "secrets.age";
+const IDENTITY_NAME: &str = "identity.txt";
+
+/// Storage operations for generic password items.
+///
+/// Every backend must report a missing item from `get` as
+/// [`Error::NotFound`] and treat deleting a missing item as success, so
+/// commands behave the same regardless of where secrets live.
+pub trait Backend {
+    /// Reads the stored value exactly as it was written.
+    fn get_bytes(&self, account: &str, service: &str) -> Result<Vec<u8>>;
+    fn set_bytes(&self, account: &str, service: &str, value: &[u8]) -> Result<()>;
+    fn delete(&self, account: &str, service: &str) -> Result<()>;
+    fn exists(&self, account: &str, service: &str) -> Result<bool>;
+    fn attributes(&self, account: &str, service: &str) -> Result<ItemAttributes>;
+
+    /// Reads the stored value as text, failing with [`Error::InvalidUtf8`]
+    /// for binary items.
+    fn get(&self, account: &str, service: &str) -> Result<String> {
+        let bytes = self.get_bytes(account, service)?;
+        String::from_utf8(bytes).map_err(|_| Error::InvalidUtf8 {
+            what: format!("secret `{}`", service),
+        })
+    }
+
+    fn set(&self, account: &str, service: &str, value: &str) -> Result<()> {
+        self.set_bytes(account, service, value.as_bytes())
+    }
+
+    /// Lists the services of every generic password stored for `account`,
+    /// including items written by other tools.
+    fn services(&self, account: &str) -> Result<Vec<String>> {
+        let _ = account;
+        Err(Error::Unsupported {
+            what: "listing keychain items".to_string(),
+        })
+    }
+
+    /// Returns the applications allowed to read the item without a prompt.
+    fn trusted_apps(&self, account: &str, service: &str) -> Result<TrustedApps> {
+        let _ = (account, service);
+        Err(Error::Unsupported {
+            what: "reading access lists".to_string(),
+        })
+    }
+
+    /// Replaces the applications allowed to read the item without a prompt.
+    fn set_trusted_apps(&self, account: &str, service: &str, apps: &[String]) -> Result<()> {
+        let _ = (account, service, apps);
+        Err(Error::Unsupported {
+            what: "changing access lists".to_string(),
+        })
+    }
+
+    /// Reads an internet password. Unset fields of `item` match any value.
+    fn get_internet(&self, item: &InternetItem) -> Result<Vec<u8>> {
+        let _ = item;
+        Err(Error::Unsupported {
+            what: "internet passwords".to_string(),
+        })
+    }
+
+    /// Creates or updates the internet password for `item`.
+    fn set_internet(&self, item: &InternetItem, value: &[u8]) -> Result<()> {
+        let _ = (item, value);
+        Err(Error::Unsupported {
+            what: "internet passwords".to_string(),
+        })
+    }
+
+    /// Deletes the internet password for `item`, succeeding if there is none.
+    fn delete_internet(&self, item: &InternetItem) -> Result<()> {
+        let _ = item;
+        Err(Error::Unsupported {
+            what: "internet passwords".to_string(),
+        })
+    }
+
+    /// Imports the certificates and private keys in `path`: a PEM or DER
+    /// certificate, or a PKCS#12 bundle decrypted with `passphrase`. `apps`
+    /// may use imported private keys without a prompt.
+    fn import_certificates(
+        &self,
+        path: &Path,
+        passphrase: Option<&str>,
+        apps: &[String],
+    ) -> Result<()> {
+        let _ = (path, passphrase, apps);
+        Err(Error::Unsupported {
+            what: "certificates".to_string(),
+        })
+    }
+
+    /// Lists the certificates in the keychain.
+    fn certificates(&self) -> Result<Vec<Certificate>> {
+        Err(Error::Unsupported {
+            what: "certificates".to_string(),
+        })
+    }
+
+    /// Returns the certificate with the SHA-1 fingerprint `sha1` in PEM form.
+    fn export_certificate(&self, sha1: &str) -> Result<String> {
+        let _ = sha1;
+        Err(Error::Unsupported {
+            what: "certificates".to_string(),
+        })
+    }
+
+    /// Sets the label and comment that Keychain Access shows for the item.
+    /// `None` leaves that attribute as it is.
+    fn describe(
+        &self,
+        account: &str,
+        service: &str,
+        label: Option<&str>,
+        comment: Option<&str>,
+    ) -> Result<()> {
+        let _ = (account, service, label, comment);
+        Err(Error::Unsupported {
+            what: "item labels and comments".to_string(),
+        })
+    }
+
+    /// Moves the item into iCloud Keychain, so it syncs to the user's other
+    /// Macs. An item that is already there is left alone.
+    fn synchronize(&self, account: &str, service: &str) -> Result<()> {
+        let _ = (account, service);
+        Err(Error::Unsupported {
+            what: "iCloud Keychain".to_string(),
+        })
+    }
+
+    /// Looks for generic passwords named `service`, or all of them, under
+    /// any account, in every keychain on the machine rather than only the
+    /// one reads use.
+    fn locate(&self, service: Option<&str>) -> Result<Search> {
+        let _ = service;
+        Err(Error::Unsupported {
+            what: "searching other keychains".to_string(),
+        })
+    }
+
+    /// Deletes one copy found by [`Backend::locate`], leaving copies in
+    /// other keychains alone.
+    fn delete_copy(&self, location: &Location) -> Result<()> {
+        let _ = location;
+        Err(Error::Unsupported {
+            what: "searching other keychains".to_string(),
+        })
+    }
+
+    /// Moves the item stored under `from` to the service name `to`. Callers
+    /// check that `to` is free first.
+    ///
+    /// The default copies the value and deletes the original, removing the
+    /// copy again if that delete fails.
+    fn rename(&self, account: &str, from: &str, to: &str) -> Result<()> {
+        let value = self.get_bytes(account, from)?;
+        self.set_bytes(account, to, &value)?;
+        if let Err(error) = self.delete(account, from) {
+            let _ = self.delete(account, to);
+            return Err(error);
+        }
+        Ok(())
+    }
+}
+
+/// Item timestamps in RFC 3339 form, and the item's description, when the
+/// keychain reports them.
+#[derive(Debug, Default)]
+pub struct ItemAttributes {
+    pub created: Option<String>,
+    pub modified: Option<String>,
+    /// The name shown in Keychain Access, when it differs from the service.
+    pub label: Option<String>,
+    pub comment: Option<String>,
+    /// Whether the item syncs through iCloud Keychain.
+    pub synchronized: bool,
+}
+
+/// Which applications may read an item without the keychain asking first.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TrustedApps {
+    /// Any application may read the item.
+    Any,
+    /// Only these applications, by path. Empty means every read prompts.
+    Only(Vec<String>),
+}
+
+/// A certificate in the keychain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Certificate {
+    /// The keychain label, usually the subject's common name.
+    pub name: String,
+    /// Upper-case hex SHA-1 fingerprint.
+    pub sha1: String,
+    /// Whether the keychain also holds the matching private key, making
+    /// this an identity usable for code signing or client TLS.
+    pub identity: bool,
+}
+
+/// A copy of a secret found by [`Backend::locate`].
+#[derive(Debug)]
+pub struct Location {
+    /// The keychain's path, or [`ICLOUD_KEYCHAIN`].
+    pub keychain: String,
+    pub account: String,
+    pub service: String,
+    pub modified: Option<String>,
+    /// Whether reads look in this keychain, as it is on the search list
+    /// or is the one the backend was opened with.
+    pub searched: bool,
+}
+
+/// The machine-wide keychain, which launch daemons read and only root can
+/// write to.
+pub const SYSTEM_KEYCHAIN: &str = "/Library/Keychains/System.keychain";
+
+/// What [`Location::keychain`] holds for items synced with iCloud Keychain.
+pub const ICLOUD_KEYCHAIN: &str = "iCloud Keychain";
+
+/// The result of [`Backend::locate`].
+#[derive(Debug, Default)]
+pub struct Search {
+    /// Copies in the order reads find them: the keychain search list first,
+    /// then the system keychain and any other keychains.
+    pub found: Vec<Location>,
+    /// Keychains that could not be read, with why.
+    pub skipped: Vec<(String, Error)>,
+}
+
+/// Protocol names accepted for internet passwords, with the four-character
+/// codes the keychain stores.
+pub const PROTOCOLS: [(&str, &str); 14] = [
+    ("http", "http"),
+    ("https", "htps"),
+    ("ftp", "ftp "),
+    ("ftps", "ftps"),
+    ("ssh", "ssh "),
+    ("smtp", "smtp"),
+    ("imap", "imap"),
+    ("imaps", "imps"),
+    ("pop3", "pop3"),
+    ("ldap", "ldap"),
+    ("ldaps", "ldps"),
+    ("smb", "smb "),
+    ("afp", "afp "),
+    ("svn", "svn "),
+];
+
+/// The attributes that identify an internet password, the item class
+/// browsers and other apps use for website and server logins.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InternetItem {
+    /// Host name, such as `api.example.com`.
+    pub server: String,
+    pub account: Option<String>,
+    /// A protocol name from [`PROTOCOLS`].
+    pub protocol: Option<String>,
+    pub path: Option<String>,
+    pub port: Option<u16>,
+}
+
+impl InternetItem {
+    /// Returns the keychain code for `protocol`, if one is set.
+    fn protocol_code(&self) -> Option<&'static str> {
+        let name = self.protocol.as_deref()?;
+        PROTOCOLS
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, code)| *code)
+    }
+}
+
+/// Formats the item as a URL, such as `https://octocat@github.com/login`.
+impl fmt::Display for InternetItem {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(protocol) = &self.protocol {
+            write!(formatter, "{}://", protocol)?;
+        }
+        if let Some(account) = &self.account {
+            write!(formatter, "{}@", account)?;
+        }
+        write!(formatter, "{}", self.server)?;
+        if let Some(port) = self.port {
+            write!(formatter, ":{}", port)?;
+        }
+        if let Some(path) = &self.path {
+            write!(formatter, "{}", path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where secrets are stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Kind {
+    /// Security.framework on macOS, the Secret Service on Linux.
+    #[default]
+    Native,
+    /// The `security` utility.
+    Security,
+    /// An age-encrypted file, for machines without a keychain.
+    File,
+}
+
+impl Kind {
+    /// Reads `KEYCHAINCTL_BACKEND` (`security` or `file`), defaulting to
+    /// [`Kind::Native`].
+    pub fn from_env() -> Self {
+        match std::env::var("KEYCHAINCTL_BACKEND").as_deref() {
+            Ok("security") => Self::Security,
+            Ok("file") => Self::File,
+            _ => Self::Native,
+        }
+    }
+}
+
+/// Returns a backend for the user's default keychain.
+pub fn default_backend() -> Box<dyn Backend> {
+    backend(Kind::from_env())
+}
+
+/// Returns a backend of `kind` for the default keychain. Keychain backends
+/// split values larger than [`crate::chunk::SIZE`] across several items.
+pub fn backend(kind: Kind) -> Box<dyn Backend> {
+    match kind {
+        #[cfg(target_os = "macos")]
+        Kind::Native => Box::new(Chunked::new(Box::new(NativeKeychain {
+            keychain: None,
+            path: None,
+        }))),
+        #[cfg(target_os = "linux")]
+        Kind::Native => Box::new(SecretService),
+        Kind::File => Box::new(EncryptedFile { path: None }),
+        _ => Box::new(Chunked::new(Box::new(SecurityCli { keychain: None }))),
+    }
+}
+
+/// Returns a backend for the keychain file at `keychain`, or the default
+/// keychain when `None`.
+///
+/// Picks the native Security.framework backend on macOS and the Secret
+/// Service on Linux. Setting `KEYCHAINCTL_BACKEND=security` falls back to
+/// the `security` utility, and `KEYCHAINCTL_BACKEND=file` uses an encrypted
+/// file.
+pub fn open(keychain: Option<&Path>) -> Result<Box<dyn Backend>> {
+    open_kind(Kind::from_env(), keychain)
+}
+
+/// Like [`open`], with the backend chosen by the caller. For
+/// [`Kind::File`], `keychain` names the encrypted file, which is created on
+/// the first write.
+pub fn open_kind(kind: Kind, keychain: Option<&Path>) -> Result<Box<dyn Backend>> {
+    let Some(path) = keychain else {
+        return Ok(backend(kind));
+    };
+    if kind == Kind::File {
+        return Ok(Box::new(EncryptedFile {
+            path: Some(path.to_path_buf()),
+        }));
+    }
+    if !path.exists() {
+        return Err(Error::KeychainMissing {
+            path: path.to_path_buf(),
+        });
+    }
+
+    #[cfg(target_os = "macos")]
+    if kind == Kind::Native {
+        return Ok(Box::new(Chunked::new(Box::new(NativeKeychain::open(
+            path,
+        )?))));
+    }
+    #[cfg(target_os = "linux")]
+    if kind == Kind::Native {
+        return Err(Error::Unsupported {
+            what: format!("opening keychain {}", path.display()),
+        });
+    }
+    Ok(Box::new(Chunked::new(Box::new(SecurityCli {
+        keychain: Some(path.to_path_buf()),
+    }))))
+}
+
+/// Creates a keychain file at `path` protected by `password`. The new
+/// keychain is left unlocked.
+pub fn create_keychain(path: &Path, password: &str) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        use security_framework::os::macos::keychain::CreateOptions;
+
+        CreateOptions::new()
+            .password(password)
+            .create(path)
+            .map_err(|error| {
+                keychain_error(
+                    format!("failed to create keychain {}", path.display()),
+                    error,
+                )
+            })?;
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = password;
+        Err(Error::Unsupported {
+            what: format!("creating keychain {}", path.display()),
+        })
+    }
+}
+
+/// Makes reads from a locked keychain fail at once for the rest of the
+/// process, instead of showing a password dialog that may never be
+/// answered. Only Security.framework can be told this; the `security`
+/// utility still asks.
+pub fn forbid_interaction() -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        use security_framework::os::macos::keychain::SecKeychain;
+
+        let guard = SecKeychain::disable_user_interaction().map_err(|error| {
+            keychain_error("failed to turn off keychain prompts".to_string(), error)
+        })?;
+        // Dropping the guard would turn prompts back on.
+        std::mem::forget(guard);
+    }
+    Ok(())
+}
+
+/// Locks `keychain`, or the default keychain, so the next read asks for
+/// its password. With `all`, every keychain is locked.
+pub fn lock_keychain(keychain: Option<&Path>, all: bool) -> Result<()> {
+    let cli = SecurityCli {
+        keychain: keychain.filter(|_| !all).map(Path::to_path_buf),
+    };
+    let args: &[&str] = if all {
+        &["lock-keychain", "-a"]
+    } else {
+        &["lock-keychain"]
+    };
+    let output = cli.output(args, "failed to lock the keychain".to_string())?;
+    if !output.status.success() {
+        return Err(security_failure(&output.stderr));
+    }
+    Ok(())
+}
+
+/// Unlocks `keychain`, or the default keychain, with `password`. The
+/// password goes through `security -i`, so it is not in the process list.
+pub fn unlock_keychain(keychain: Option<&Path>, password: &str) -> Result<()> {
+    let cli = SecurityCli {
+        keychain: keychain.map(Path::to_path_buf),
+    };
+    cli.interactive(
+        &["unlock-keychain", "-p", password],
+        "failed to unlock the keychain".to_string(),
+    )
+}
+
+/// Makes `keychain`, or the default keychain, lock itself after `timeout`
+/// seconds without use, or never when it is `None`. Whether it also locks
+/// when the Mac sleeps is kept as it was, since `set-keychain-settings`
+/// resets every setting it is not given.
+pub fn set_lock_timeout(keychain: Option<&Path>, timeout: Option<u64>) -> Result<()> {
+    let cli = SecurityCli {
+        keychain: keychain.map(Path::to_path_buf),
+    };
+    let action = || "failed to change the keychain settings".to_string();
+    let info = cli.output(&["show-keychain-info"], action())?;
+    if !info.status.success() {
+        return Err(security_failure(&info.stderr));
+    }
+    // The settings are printed on stderr, as `Keychain "<path>"
+    // lock-on-sleep timeout=300s`.
+    let lock_on_sleep = [&info.stdout, &info.stderr]
+        .iter()
+        .any(|stream| String::from_utf8_lossy(stream).contains("lock-on-sleep"));
+
+    let timeout = timeout.map(|seconds| seconds.to_string());
+    let mut args = vec!["set-keychain-settings"];
+    if lock_on_sleep {
+        args.push("-l");
+    }
+    if let Some(timeout) = &timeout {
+        args.extend(["-u", "-t", timeout.as_str()]);
+    }
+    let output = cli.output(&args, action())?;
+    if !output.status.success() {
+        return Err(security_failure(&output.stderr));
+    }
+    Ok(())
+}
+
+/// Talks to Security.framework directly, so secret values never appear in
+/// the argument list of a child process.
+#[cfg(target_os = "macos")]
+pub struct NativeKeychain {
+    keychain: Option<security_framework::os::macos::keychain::SecKeychain>,
+    path: Option<PathBuf>,
+}
+
+#[cfg(target_os = "macos")]
+const ERR_SEC_ITEM_NOT_FOUND: i32 = -25300;
+
+#[cfg(target_os = "macos")]
+fn keychain_error(action: String, error: security_framework::base::Error) -> Error {
+    Error::Keychain {
+        action,
+        code: error.code(),
+        message: error.to_string(),
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl NativeKeychain {
+    pub fn open(path: &Path) -> Result<Self> {
+        use security_framework::os::macos::keychain::SecKeychain;
+
+        let keychain = SecKeychain::open(path).map_err(|error| {
+            keychain_error(format!("failed to open keychain {}", path.display()), error)
+        })?;
+        Ok(Self {
+            keychain: Some(keychain),
+            path: Some(path.to_path_buf()),
+        })
+    }
+
+    /// Security.framework's access list API is deprecated and not exposed,
+    /// and its certificate export is not either, so access lists and
+    /// certificates go through the `security` utility.
+    fn security_cli(&self) -> SecurityCli {
+        SecurityCli {
+            keychain: self.path.clone(),
+        }
+    }
+
+    /// The keychain to write internet passwords to, which the legacy
+    /// keychain API needs explicitly.
+    fn write_keychain(&self) -> Result<security_framework::os::macos::keychain::SecKeychain> {
+        use security_framework::os::macos::keychain::SecKeychain;
+
+        match &self.keychain {
+            Some(keychain) => Ok(keychain.clone()),
+            None => SecKeychain::default().map_err(|error| {
+                keychain_error("failed to open the default keychain".to_string(), error)
+            }),
+        }
+    }
+
+    /// Looks up an internet password with `SecKeychainFindInternetPassword`,
+    /// which treats empty attributes as wildcards, unlike `SecItem` queries.
+    fn find_internet(
+        &self,
+        item: &InternetItem,
+    ) -> std::result::Result<
+        (
+            security_framework::os::macos::passwords::SecKeychainItemPassword,
+            security_framework::os::macos::keychain_item::SecKeychainItem,
+        ),
+        security_framework::base::Error,
+    > {
+        use security_framework::os::macos::passwords::{
+            SecAuthenticationType, find_internet_password,
+        };
+
+        find_internet_password(
+            self.keychain.as_ref().map(std::slice::from_ref),
+            &item.server,
+            None,
+            item.account.as_deref().unwrap_or_default(),
+            item.path.as_deref().unwrap_or_default(),
+            item.port,
+            native_protocol(item.protocol_code()),
+            SecAuthenticationType::Any,
+        )
+    }
+
+    fn query(&self, account: &str, service: &str) -> security_framework::item::ItemSearchOptions {
+        let mut options = self.account_query(account);
+        options.service(service);
+        options
+    }
+
+    /// Matches the iCloud Keychain copy of an item, which only the default
+    /// keychain can have.
+    fn synced(account: &str, service: &str) -> security_framework::passwords::PasswordOptions {
+        let mut options =
+            security_framework::passwords::PasswordOptions::new_generic_password(service, account);
+        options.set_access_synchronized(Some(true));
+        options
+    }
+
+    /// Whether the item is in iCloud Keychain and not also in the login
+    /// keychain, whose copy reads and writes would otherwise use.
+    fn only_synced(&self, account: &str, service: &str) -> bool {
+        use security_framework::item::{CloudSync, ItemClass, ItemSearchOptions, Limit};
+
+        let found = |options: &mut ItemSearchOptions| {
+            options
+                .limit(Limit::Max(1))
+                .search()
+                .is_ok_and(|results| !results.is_empty())
+        };
+        self.keychain.is_none()
+            && !found(&mut self.query(account, service))
+            && found(
+                ItemSearchOptions::new()
+                    .class(ItemClass::generic_password())
+                    .account(account)
+                    .service(service)
+                    .cloud_sync(CloudSync::MatchSyncYes),
+            )
+    }
+
+    fn account_query(&self, account: &str) -> security_framework::item::ItemSearchOptions {
+        use security_framework::item::{ItemClass, ItemSearchOptions};
+
+        let mut options = ItemSearchOptions::new();
+        options
+            .class(ItemClass::generic_password())
+            .account(account);
+        if let Some(keychain) = &self.keychain {
+            options.keychains(std::slice::from_ref(keychain));
+        }
+        options
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl Backend for NativeKeychain {
+    fn get_bytes(&self, account: &str, service: &str) -> Result<Vec<u8>> {
+        let result = match &self.keychain {
+            Some(keychain) => keychain
+                .find_generic_password(service, account)
+                .map(|(password, _)| password.to_vec()),
+            // An item only in iCloud Keychain is not matched by default.
+            // Builds without the entitlement it needs cannot read it, and
+            // report the item as missing.
+            None => security_framework::passwords::get_generic_password(service, account).or_else(
+                |error| match error.code() {
+                    ERR_SEC_ITEM_NOT_FOUND => security_framework::passwords::generic_password(
+                        Self::synced(account, service),
+                    )
+                    .map_err(|_| error),
+                    _ => Err(error),
+                },
+            ),
+        };
+        match result {
+            Ok(bytes) => Ok(bytes),
+            Err(error) if error.code() == ERR_SEC_ITEM_NOT_FOUND => Err(Error::NotFound {
+                service: service.to_string(),
+            }),
+            Err(error) => Err(keychain_error(
+                format!("failed to read secret `{}`", service),
+                error,
+            )),
+        }
+    }
+
+    fn set_bytes(&self, account: &str, service: &str, value: &[u8]) -> Result<()> {
+        let result = match &self.keychain {
+            Some(keychain) => keychain.set_generic_password(service, account, value),
+            None if self.only_synced(account, service) => {
+                security_framework::passwords::set_generic_password_options(
+                    value,
+                    Self::synced(account, service),
+                )
+            }
+            None => security_framework::passwords::set_generic_password(service, account, value),
+        };
+        result
+            .map_err(|error| keychain_error(format!("failed to store secret `{}`", service), error))
+    }
+
+    fn delete(&self, account: &str, service: &str) -> Result<()> {
+        let result = match &self.keychain {
+            Some(keychain) => keychain
+                .find_generic_password(service, account)
+                .map(|(_, item)| item.delete()),
+            None if self.only_synced(account, service) => {
+                security_framework::passwords::delete_generic_password_options(Self::synced(
+                    account, service,
+                ))
+            }
+            None => security_framework::passwords::delete_generic_password(service, account),
+        };
+        match result {
+            Ok(()) => Ok(()),
+            Err(error) if error.code() == ERR_SEC_ITEM_NOT_FOUND => Ok(()),
+            Err(error) => Err(keychain_error(
+                format!("failed to delete secret `{}`", service),
+                error,
+            )),
+        }
+    }
+
+    fn exists(&self, account: &str, service: &str) -> Result<bool> {
+        use security_framework::item::Limit;
+
+        match self.query(account, service).limit(Limit::Max(1)).search() {
+            Ok(results) if !results.is_empty() => Ok(true),
+            Ok(_) => Ok(self.only_synced(account, service)),
+            Err(error) if error.code() == ERR_SEC_ITEM_NOT_FOUND => {
+                Ok(self.only_synced(account, service))
+            }
+            Err(error) => Err(keychain_error(
+                format!("failed to look up `{}`", service),
+                error,
+            )),
+        }
+    }
+
+    fn attributes(&self, account: &str, service: &str) -> Result<ItemAttributes> {
+        use security_framework::item::{CloudSync, Limit};
+
+        let synchronized = self.only_synced(account, service);
+        let mut query = self.query(account, service);
+        if synchronized {
+            query.cloud_sync(CloudSync::MatchSyncYes);
+        }
+        let results = query
+            .limit(Limit::Max(1))
+            .load_attributes(true)
+            .search()
+            .map_err(|error| {
+                keychain_error(
+                    format!("failed to read attributes for `{}`", service),
+                    error,
+                )
+            })?;
+
+        let mut attributes = ItemAttributes {
+            synchronized,
+            ..ItemAttributes::default()
+        };
+        if let Some(dict) = results.first().and_then(|result| result.simplify_dict()) {
+            attributes.created = dict.get("cdat").and_then(|date| parse_cfdate(date));
+            attributes.modified = dict.get("mdat").and_then(|date| parse_cfdate(date));
+            attributes.label = dict.get("labl").filter(|label| *label != service).cloned();
+            attributes.comment = dict
+                .get("icmt")
+                .filter(|comment| !comment.is_empty())
+                .cloned();
+        }
+        Ok(attributes)
+    }
+
+    /// Adds a synced copy and then deletes the local one, so a failure
+    /// leaves the value where it was.
+    fn synchronize(&self, account: &str, service: &str) -> Result<()> {
+        use security_framework::passwords::{
+            PasswordOptions, delete_generic_password_options, get_generic_password,
+            set_generic_password_options,
+        };
+
+        if self.keychain.is_some() {
+            return Err(Error::Unsupported {
+                what: "iCloud Keychain for a keychain file".to_string(),
+            });
+        }
+        let value = match get_generic_password(service, account) {
+            Ok(value) => crate::secret::Zeroizing::new(value),
+            Err(error) if error.code() == ERR_SEC_ITEM_NOT_FOUND => {
+                return match self.only_synced(account, service) {
+                    true => Ok(()),
+                    false => Err(Error::NotFound {
+                        service: service.to_string(),
+                    }),
+                };
+            }
+            Err(error) => {
+                return Err(keychain_error(
+                    format!("failed to read secret `{}`", service),
+                    error,
+                ));
+            }
+        };
+        let action = || format!("failed to sync `{}` with iCloud Keychain", service);
+        set_generic_password_options(&value, Self::synced(account, service))
+            .map_err(|error| keychain_error(action(), error))?;
+        let mut local = PasswordOptions::new_generic_password(service, account);
+        local.set_access_synchronized(Some(false));
+        delete_generic_password_options(local).map_err(|error| keychain_error(action(), error))
+    }
+
+    /// Adds items synced with iCloud Keychain, which live outside the file
+    /// keychains `security` can read.
+    fn locate(&self, service: Option<&str>) -> Result<Search> {
+        use security_framework::item::{CloudSync, ItemClass, ItemSearchOptions, Limit};
+
+        let mut search = self.security_cli().locate(service)?;
+        let mut options = ItemSearchOptions::new();
+        options
+            .class(ItemClass::generic_password())
+            .cloud_sync(CloudSync::MatchSyncYes)
+            .load_attributes(true)
+            .limit(Limit::All);
+        if let Some(service) = service {
+            options.service(service);
+        }
+        let results = options.search();
+        match results {
+            Ok(results) => {
+                for dict in results.iter().filter_map(|result| result.simplify_dict()) {
+                    search.found.push(Location {
+                        keychain: ICLOUD_KEYCHAIN.to_string(),
+                        account: dict.get("acct").cloned().unwrap_or_default(),
+                        service: dict.get("svce").cloned().unwrap_or_default(),
+                        modified: dict.get("mdat").and_then(|date| parse_cfdate(date)),
+                        searched: false,
+                    });
+                }
+            }
+            Err(error) if error.code() == ERR_SEC_ITEM_NOT_FOUND => {}
+            Err(error) => search.skipped.push((
+                ICLOUD_KEYCHAIN.to_string(),
+                keychain_error("failed to search iCloud Keychain".to_string(), error),
+            )),
+        }
+        Ok(search)
+    }
+
+    fn delete_copy(&self, location: &Location) -> Result<()> {
+        use security_framework::item::{CloudSync, ItemClass, ItemSearchOptions};
+
+        if location.keychain != ICLOUD_KEYCHAIN {
+            return self.security_cli().delete_copy(location);
+        }
+        ItemSearchOptions::new()
+            .class(ItemClass::generic_password())
+            .account(&location.account)
+            .service(&location.service)
+            .cloud_sync(CloudSync::MatchSyncYes)
+            .delete()
+            .map_err(|error| match error.code() {
+                ERR_SEC_ITEM_NOT_FOUND => Error::NotFound {
+                    service: location.service.clone(),
+                },
+                _ => keychain_error(
+                    format!(
+                        "failed to delete `{}` from iCloud Keychain",
+                        location.service
+                    ),
+                    error,
+                ),
+            })
+    }
+
+    fn describe(
+        &self,
+        account: &str,
+        service: &str,
+        label: Option<&str>,
+        comment: Option<&str>,
+    ) -> Result<()> {
+        use security_framework::item::{ItemUpdateOptions, update_item};
+
+        let mut changes = ItemUpdateOptions::new();
+        if let Some(label) = label {
+            changes.set_label(label);
+        }
+        if let Some(comment) = comment {
+            changes.set_comment(comment);
+        }
+        match update_item(&self.query(account, service), &changes) {
+            Ok(()) => Ok(()),
+            Err(error) if error.code() == ERR_SEC_ITEM_NOT_FOUND => Err(Error::NotFound {
+                service: service.to_string(),
+            }),
+            Err(error) => Err(keychain_error(
+                format!("failed to describe secret `{}`", service),
+                error,
+            )),
+        }
+    }
+
+    fn services(&self, account: &str) -> Result<Vec<String>> {
+        use security_framework::item::Limit;
+
+        match self
+            .account_query(account)
+            .limit(Limit::All)
+            .load_attributes(true)
+            .search()
+        {
+            Ok(results) => Ok(results
+                .iter()
+                .filter_map(|result| result.simplify_dict()?.get("svce").cloned())
+                .collect()),
+            Err(error) if error.code() == ERR_SEC_ITEM_NOT_FOUND => Ok(Vec::new()),
+            Err(error) => Err(keychain_error(
+                "failed to list keychain items".to_string(),
+                error,
+            )),
+        }
+    }
+
+    fn trusted_apps(&self, account: &str, service: &str) -> Result<TrustedApps> {
+        self.security_cli().trusted_apps(account, service)
+    }
+
+    fn set_trusted_apps(&self, account: &str, service: &str, apps: &[String]) -> Result<()> {
+        self.security_cli().set_trusted_apps(account, service, apps)
+    }
+
+    fn import_certificates(
+        &self,
+        path: &Path,
+        passphrase: Option<&str>,
+        apps: &[String],
+    ) -> Result<()> {
+        self.security_cli()
+            .import_certificates(path, passphrase, apps)
+    }
+
+    fn certificates(&self) -> Result<Vec<Certificate>> {
+        self.security_cli().certificates()
+    }
+
+    fn export_certificate(&self, sha1: &str) -> Result<String> {
+        self.security_cli().export_certificate(sha1)
+    }
+
+    fn get_internet(&self, item: &InternetItem) -> Result<Vec<u8>> {
+        match self.find_internet(item) {
+            Ok((password, _)) => Ok(password.to_vec()),
+            Err(error) if error.code() == ERR_SEC_ITEM_NOT_FOUND => Err(Error::NotFound {
+                service: item.to_string(),
+            }),
+            Err(error) => Err(keychain_error(format!("failed to read {}", item), error)),
+        }
+    }
+
+    fn set_internet(&self, item: &InternetItem, value: &[u8]) -> Result<()> {
+        use security_framework::os::macos::passwords::SecAuthenticationType;
+
+        self.write_keychain()?
+            .set_internet_password(
+                &item.server,
+                None,
+                item.account.as_deref().unwrap_or_default(),
+                item.path.as_deref().unwrap_or_default(),
+                item.port,
+                native_protocol(item.protocol_code()),
+                SecAuthenticationType::Default,
+                value,
+            )
+            .map_err(|error| keychain_error(format!("failed to store {}", item), error))
+    }
+
+    fn delete_internet(&self, item: &InternetItem) -> Result<()> {
+        match self.find_internet(item) {
+            Ok((_, found)) => {
+                found.delete();
+                Ok(())
+            }
+            Err(error) if error.code() == ERR_SEC_ITEM_NOT_FOUND => Ok(()),
+            Err(error) => Err(keychain_error(format!("failed to delete {}", item), error)),
+        }
+    }
+
+    /// Renames in place with `SecItemUpdate`, so the item keeps its access
+    /// control list, label, comment, and creation date.
+    fn rename(&self, account: &str, from: &str, to: &str) -> Result<()> {
+        use security_framework::item::{ItemUpdateOptions, update_item};
+
+        let mut changes = ItemUpdateOptions::new();
+        changes.set_service(to);
+        match update_item(&self.query(account, from), &changes) {
+            Ok(()) => Ok(()),
+            Err(error) if error.code() == ERR_SEC_ITEM_NOT_FOUND => Err(Error::NotFound {
+                service: from.to_string(),
+            }),
+            Err(error) => Err(keychain_error(
+                format!("failed to rename secret `{}`", from),
+                error,
+            )),
+        }
+    }
+}
+
+/// Maps a four-character protocol code to Security.framework's type. `None`
+/// matches any protocol.
+#[cfg(target_os = "macos")]
+fn native_protocol(
+    code: Option<&str>,
+) -> security_framework::os::macos::passwords::SecProtocolType {
+    use security_framework::os::macos::passwords::SecProtocolType;
+
+    match code {
+        Some("http") => SecProtocolType::HTTP,
+        Some("htps") => SecProtocolType::HTTPS,
+        Some("ftp ") => SecProtocolType::FTP,
+        Some("ftps") => SecProtocolType::FTPS,
+        Some("ssh ") => SecProtocolType::SSH,
+        Some("smtp") => SecProtocolType::SMTP,
+        Some("imap") => SecProtocolType::IMAP,
+        Some("imps") => SecProtocolType::IMAPS,
+        Some("pop3") => SecProtocolType::POP3,
+        Some("ldap") => SecProtocolType::LDAP,
+        Some("ldps") => SecProtocolType::LDAPS,
+        Some("smb ") => SecProtocolType::SMB,
+        Some("afp ") => SecProtocolType::AFP,
+        Some("svn ") => SecProtocolType::SVN,
+        _ => SecProtocolType::Any,
+    }
+}
+
+/// Converts a CFDate description such as `2024-01-02 03:04:05 +0000` into
+/// RFC 3339.
+#[cfg(target_os = "macos")]
+fn parse_cfdate(description: &str) -> Option<String> {
+    let (date, rest) = description.split_once(' ')?;
+    let time = rest.split(' ').next()?;
+    Some(format!("{}T{}Z", date, time))
+}
+
+/// Stores items in the freedesktop Secret Service (GNOME Keyring or
+/// KWallet) through libsecret's `secret-tool`.
+///
+/// Items are matched on `service` and `username` attributes, the same ones
+/// Python's `keyring` uses. Values are passed on stdin and stdout, never in
+/// arguments.
+#[cfg(target_os = "linux")]
+pub struct SecretService;
+
+#[cfg(target_os = "linux")]
+impl SecretService {
+    fn command(&self, args: &[&str], account: &str, service: &str) -> Command {
+        let mut command = Command::new(SECRET_TOOL_BIN);
+        command
+            .args(args)
+            .args(["service", service, "username", account]);
+        command
+    }
+
+    fn output(&self, command: &mut Command, action: String) -> Result<Output> {
+        command
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|error| Error::io(action, error))
+    }
+}
+
+/// `secret-tool` exits with status 1 and prints nothing when no item
+/// matches.
+#[cfg(target_os = "linux")]
+fn secret_tool_missing(output: &Output) -> bool {
+    output.status.code() == Some(1) && output.stderr.iter().all(u8::is_ascii_whitespace)
+}
+
+#[cfg(target_os = "linux")]
+fn secret_tool_failure(output: &Output) -> Error {
+    let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    Error::SecretService {
+        message: if message.is_empty() {
+            format!("exited with status {}", output.status)
+        } else {
+            message
+        },
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Backend for SecretService {
+    fn get_bytes(&self, account: &str, service: &str) -> Result<Vec<u8>> {
+        let output = self.output(
+            &mut self.command(&["lookup"], account, service),
+            format!("failed to read secret `{}`", service),
+        )?;
+        if output.status.success() {
+            return Ok(output.stdout);
+        }
+        if secret_tool_missing(&output) {
+            return Err(Error::NotFound {
+                service: service.to_string(),
+            });
+        }
+        Err(secret_tool_failure(&output))
+    }
+
+    fn set_bytes(&self, account: &str, service: &str, value: &[u8]) -> Result<()> {
+        use std::io::Write;
+
+        let action = || format!("failed to store secret `{}`", service);
+        let label = format!("--label={} ({})", service, account);
+        let mut child = self
+            .command(&["store", &label], account, service)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|error| Error::io(action(), error))?;
+        let written = child.stdin.take().expect("stdin is piped").write_all(value);
+        let output = child
+            .wait_with_output()
+            .map_err(|error| Error::io(action(), error))?;
+        if !output.status.success() {
+            return Err(secret_tool_failure(&output));
+        }
+        written.map_err(|error| Error::io(action(), error))
+    }
+
+    fn delete(&self, account: &str, service: &str) -> Result<()> {
+        let output = self.output(
+            &mut self.command(&["clear"], account, service),
+            format!("failed to delete secret `{}`", service),
+        )?;
+        if output.status.success() || secret_tool_missing(&output) {
+            return Ok(());
+        }
+        Err(secret_tool_failure(&output))
+    }
+
+    fn exists(&self, account: &str, service: &str) -> Result<bool> {
+        match self.get_bytes(account, service) {
+            Ok(_) => Ok(true),
+            Err(Error::NotFound { .. }) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// `secret-tool search` prints timestamps in local time, so it runs with
+    /// `TZ=UTC`.
+    fn attributes(&self, account: &str, service: &str) -> Result<ItemAttributes> {
+        let output = self.output(
+            self.command(&["search"], account, service).env("TZ", "UTC"),
+            format!("failed to read attributes for `{}`", service),
+        )?;
+        if !output.status.success() {
+            return Err(secret_tool_failure(&output));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !stdout.lines().any(|line| line.starts_with('[')) {
+            return Err(Error::NotFound {
+                service: service.to_string(),
+            });
+        }
+        let mut attributes = ItemAttributes::default();
+        for line in stdout.lines() {
+            let Some((key, value)) = line.split_once(" = ") else {
+                continue;
+            };
+            let timestamp = value
+                .split_once(' ')
+                .map(|(date, time)| format!("{}T{}Z", date, time));
+            match key {
+                "created" => attributes.created = timestamp,
+                "modified" => attributes.modified = timestamp,
+                _ => {}
+            }
+        }
+        Ok(attributes)
+    }
+
+    fn services(&self, account: &str) -> Result<Vec<String>> {
+        let output = self.output(
+            Command::new(SECRET_TOOL_BIN).args(["search", "--all", "username", account]),
+            "failed to list secrets".to_string(),
+        )?;
+        if !output.status.success() && !secret_tool_missing(&output) {
+            return Err(secret_tool_failure(&output));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.strip_prefix("attribute.service = "))
+            .map(str::to_string)
+            .collect())
+    }
+}
+
+/// Keeps every secret in one file encrypted with `age`, for CI runners and
+/// servers without a keychain.
+///
+/// The file is `secrets.age` in the config directory unless a path is
+/// given. It is encrypted to the X25519 identity in
+/// `$KEYCHAINCTL_FILE_IDENTITY`, or `identity.txt` in the config
+/// directory, which is generated with `age-keygen` on the first write.
+/// Each change rewrites the whole file through a temporary file.
+pub struct EncryptedFile {
+    path: Option<PathBuf>,
+}
+
+/// The decrypted contents of an [`EncryptedFile`].
+#[derive(Default, Deserialize, Serialize)]
+struct Items {
+    #[serde(default, rename = "item")]
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Item {
+    account: String,
+    service: String,
+    /// The value, hex-encoded so binary secrets survive.
+    value: String,
+    created: String,
+    modified: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+impl EncryptedFile {
+    fn path(&self) -> Result<PathBuf> {
+        match &self.path {
+            Some(path) => Ok(path.clone()),
+            None => Ok(config::dir()?.join(FILE_NAME)),
+        }
+    }
+
+    fn identity() -> Result<PathBuf> {
+        match std::env::var_os("KEYCHAINCTL_FILE_IDENTITY") {
+            Some(path) if !path.is_empty() => Ok(PathBuf::from(path)),
+            _ => Ok(config::dir()?.join(IDENTITY_NAME)),
+        }
+    }
+
+    fn load(&self) -> Result<Items> {
+        let path = self.path()?;
+        if !path.exists() {
+            return Ok(Items::default());
+        }
+        let output = Command::new(AGE_BIN)
+            .arg("--decrypt")
+            .arg("--identity")
+            .arg(Self::identity()?)
+            .arg(&path)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|error| Error::io("failed to run age", error))?;
+        if !output.status.success() {
+            return Err(age_failure(&output));
+        }
+        let data = String::from_utf8(output.stdout).map_err(|_| Error::InvalidUtf8 {
+            what: path.display().to_string(),
+        })?;
+        toml::from_str(&data).map_err(|error| Error::InvalidConfig {
+            path,
+            message: error.message().to_string(),
+        })
+    }
+
+    fn save(&self, items: &Items) -> Result<()> {
+        let path = self.path()?;
+        let identity = Self::identity()?;
+        for parent in [path.parent(), identity.parent()].into_iter().flatten() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent).map_err(|error| {
+                    Error::io(format!("failed to create {}", parent.display()), error)
+                })?;
+            }
+        }
+        if !identity.exists() {
+            generate_identity(&identity)?;
+        }
+
+        let data = toml::to_string(items).map_err(|error| Error::InvalidConfig {
+            path: path.clone(),
+            message: error.to_string(),
+        })?;
+        let temporary = path.with_extension("tmp");
+        let mut child = Command::new(AGE_BIN)
+            .arg("--encrypt")
+            .arg("--identity")
+            .arg(&identity)
+            .arg("--output")
+            .arg(&temporary)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|error| Error::io("failed to run age", error))?;
+        let written = child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(data.as_bytes());
+        let output = child
+            .wait_with_output()
+            .map_err(|error| Error::io("failed to run age", error))?;
+        if !output.status.success() {
+            let _ = fs::remove_file(&temporary);
+            return Err(age_failure(&output));
+        }
+        written.map_err(|error| Error::io("failed to pass secrets to age", error))?;
+        fs::rename(&temporary, &path)
+            .map_err(|error| Error::io(format!("failed to write {}", path.display()), error))
+    }
+
+    fn find<'a>(items: &'a [Item], account: &str, service: &str) -> Result<&'a Item> {
+        items
+            .iter()
+            .find(|item| item.account == account && item.service == service)
+            .ok_or_else(|| Error::NotFound {
+                service: service.to_string(),
+            })
+    }
+}
+
+fn generate_identity(path: &Path) -> Result<()> {
+    let output = Command::new(AGE_KEYGEN_BIN)
+        .arg("--output")
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|error| Error::io("failed to run age-keygen", error))?;
+    if !output.status.success() {
+        return Err(age_failure(&output));
+    }
+    Ok(())
+}
+
+fn age_failure(output: &Output) -> Error {
+    let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    Error::Age {
+        message: if message.is_empty() {
+            format!("exited with status {}", output.status)
+        } else {
+            message
+        },
+    }
+}
+
+impl Backend for EncryptedFile {
+    fn get_bytes(&self, account: &str, service: &str) -> Result<Vec<u8>> {
+        let items = self.load()?;
+        let item = Self::find(&items.items, account, service)?;
+        hex_decode(&item.value).ok_or_else(|| Error::Age {
+            message: format!("the stored value of `{}` is not valid hex", service),
+        })
+    }
+
+    fn set_bytes(&self, account: &str, service: &str, value: &[u8]) -> Result<()> {
+        let mut items = self.load()?;
+        let now = date::timestamp();
+        let value = hex_encode(value);
+        match items
+            .items
+            .iter_mut()
+            .find(|item| item.account == account && item.service == service)
+        {
+            Some(item) => {
+                item.value = value;
+                item.modified = now;
+            }
+            None => items.items.push(Item {
+                account: account.to_string(),
+                service: service.to_string(),
+                value,
+                created: now.clone(),
+                modified: now,
+                label: None,
+                comment: None,
+            }),
+        }
+        self.save(&items)
+    }
+
+    fn delete(&self, account: &str, service: &str) -> Result<()> {
+        let mut items = self.load()?;
+        let count = items.items.len();
+        items
+            .items
+            .retain(|item| item.account != account || item.service != service);
+        if items.items.len() == count {
+            return Ok(());
+        }
+        self.save(&items)
+    }
+
+    fn exists(&self, account: &str, service: &str) -> Result<bool> {
+        let items = self.load()?;
+        Ok(Self::find(&items.items, account, service).is_ok())
+    }
+
+    fn attributes(&self, account: &str, service: &str) -> Result<ItemAttributes> {
+        let items = self.load()?;
+        let item = Self::find(&items.items, account, service)?;
+        Ok(ItemAttributes {
+            created: Some(item.created.clone()),
+            modified: Some(item.modified.clone()),
+            label: item.label.clone(),
+            comment: item.comment.clone(),
+            synchronized: false,
+        })
+    }
+
+    fn describe(
+        &self,
+        account: &str,
+        service: &str,
+        label: Option<&str>,
+        comment: Option<&str>,
+    ) -> Result<()> {
+        let mut items = self.load()?;
+        let item = items
+            .items
+            .iter_mut()
+            .find(|item| item.account == account && item.service == service)
+            .ok_or_else(|| Error::NotFound {
+                service: service.to_string(),
+            })?;
+        if let Some(label) = label {
+            item.label = Some(label.to_string()).filter(|label| label != service);
+        }
+        if let Some(comment) = comment {
+            item.comment = Some(comment.to_string()).filter(|comment| !comment.is_empty());
+        }
+        item.modified = date::timestamp();
+        self.save(&items)
+    }
+
+    fn services(&self, account: &str) -> Result<Vec<String>> {
+        Ok(self
+            .load()?
+            .items
+            .into_iter()
+            .filter(|item| item.account == account)
+            .map(|item| item.service)
+            .collect())
+    }
+}
+
+/// Shells out to the `security` utility.
+pub struct SecurityCli {
+    keychain: Option<PathBuf>,
+}
+
+impl SecurityCli {
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(SECURITY_BIN);
+        command.args(args);
+        if let Some(keychain) = &self.keychain {
+            command.arg(keychain);
+        }
+        command
+    }
+
+    fn output(&self, args: &[&str], action: String) -> Result<Output> {
+        self.command(args)
+            .output()
+            .map_err(|error| Error::io(action, error))
+    }
+
+    /// Runs one command through `security -i`, which reads it from stdin,
+    /// so values written with it never appear in the process list.
+    fn interactive(&self, args: &[&str], action: String) -> Result<()> {
+        let keychain = self
+            .keychain
+            .as_ref()
+            .map(|keychain| keychain.to_string_lossy());
+        let mut args = args.to_vec();
+        args.extend(keychain.as_deref());
+        run_interactive(&args, action)
+    }
+}
+
+/// Runs `args` as one `security -i` command line, without adding the
+/// keychain.
+fn run_interactive(args: &[&str], action: String) -> Result<()> {
+    if args.iter().any(|arg| arg.contains(['\n', '\r'])) {
+        return Err(Error::Security {
+            message: "names and passphrases cannot contain line breaks".to_string(),
+        });
+    }
+    let words: Vec<String> = args.iter().map(|arg| interactive_quote(arg)).collect();
+    let mut line = words.join(" ");
+    line.push('\n');
+
+    let mut child = Command::new(SECURITY_BIN)
+        .arg("-i")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| Error::io(action.clone(), error))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(line.as_bytes())
+            .map_err(|error| Error::io(action.clone(), error))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|error| Error::io(action, error))?;
+    // `security -i` exits 0 even when the command fails, so a failure
+    // is recognized by its message.
+    if !output.status.success() || !output.stderr.trim_ascii().is_empty() {
+        return Err(security_failure(&output.stderr));
+    }
+    Ok(())
+}
+
+/// Quotes a word for a `security -i` command line.
+fn interactive_quote(word: &str) -> String {
+    let mut quoted = String::from("\"");
+    for character in word.chars() {
+        if matches!(character, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(character);
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn security_failure(stderr: &[u8]) -> Error {
+    Error::Security {
+        message: String::from_utf8_lossy(stderr).trim().to_string(),
+    }
+}
+
+fn is_not_found(stderr: &[u8]) -> bool {
+    String::from_utf8_lossy(stderr).contains("could not be found")
+}
+
+impl SecurityCli {
+    fn find(&self, account: &str, service: &str, flag: &str) -> Result<Output> {
+        let output = self.output(
+            &["find-generic-password", flag, "-a", account, "-s", service],
+            format!("failed to read secret `{}`", service),
+        )?;
+
+        if !output.status.success() {
+            if is_not_found(&output.stderr) {
+                return Err(Error::NotFound {
+                    service: service.to_string(),
+                });
+            }
+            return Err(security_failure(&output.stderr));
+        }
+        Ok(output)
+    }
+
+    /// Builds the attribute flags shared by the `*-internet-password`
+    /// commands. Unset fields are left out so they match anything.
+    fn internet_args(item: &InternetItem) -> Vec<String> {
+        let mut args = vec!["-s".to_string(), item.server.clone()];
+        if let Some(account) = &item.account {
+            args.extend(["-a".to_string(), account.clone()]);
+        }
+        if let Some(code) = item.protocol_code() {
+            args.extend(["-r".to_string(), code.to_string()]);
+        }
+        if let Some(path) = &item.path {
+            args.extend(["-p".to_string(), path.clone()]);
+        }
+        if let Some(port) = item.port {
+            args.extend(["-P".to_string(), port.to_string()]);
+        }
+        args
+    }
+
+    /// Stores `value` hex-encoded with `-X`, so any bytes survive, through
+    /// `security -i` so the value is not in the process arguments.
+    fn add(&self, account: &str, service: &str, value: &[u8]) -> Result<()> {
+        let hex = hex_encode(value);
+        self.interactive(
+            &[
+                "add-generic-password",
+                "-a",
+                account,
+                "-s",
+                service,
+                "-X",
+                &hex,
+                "-U",
+            ],
+            format!("failed to store secret `{}`", service),
+        )
+    }
+}
+
+impl Backend for SecurityCli {
+    /// Uses `-g`, which prints the raw value on stderr, hex-encoded when it
+    /// is not printable.
+    fn get_bytes(&self, account: &str, service: &str) -> Result<Vec<u8>> {
+        let output = self.find(account, service, "-g")?;
+        String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .find_map(|line| line.strip_prefix("password:"))
+            .and_then(parse_password_line)
+            .ok_or_else(|| Error::Security {
+                message: format!("could not parse the value of `{}`", service),
+            })
+    }
+
+    fn set_bytes(&self, account: &str, service: &str, value: &[u8]) -> Result<()> {
+        self.add(account, service, value)
+    }
+
+    fn delete(&self, account: &str, service: &str) -> Result<()> {
+        let output = self.output(
+            &["delete-generic-password", "-a", account, "-s", service],
+            format!("failed to delete secret `{}`", service),
+        )?;
+
+        if output.status.success() || is_not_found(&output.stderr) {
+            return Ok(());
+        }
+
+        Err(security_failure(&output.stderr))
+    }
+
+    fn exists(&self, account: &str, service: &str) -> Result<bool> {
+        let output = self.output(
+            &["find-generic-password", "-a", account, "-s", service],
+            format!("failed to look up `{}`", service),
+        )?;
+
+        if output.status.success() {
+            return Ok(true);
+        }
+        if is_not_found(&output.stderr) {
+            return Ok(false);
+        }
+
+        Err(security_failure(&output.stderr))
+    }
+
+    fn attributes(&self, account: &str, service: &str) -> Result<ItemAttributes> {
+        let output = self.output(
+            &["find-generic-password", "-a", account, "-s", service],
+            format!("failed to read attributes for `{}`", service),
+        )?;
+
+        if !output.status.success() {
+            return Err(security_failure(&output.stderr));
+        }
+
+        let mut attributes = ItemAttributes::default();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let line = line.trim();
+            if line.starts_with("\"cdat\"<timedate>=") {
+                attributes.created = parse_timedate(line);
+            } else if line.starts_with("\"mdat\"<timedate>=") {
+                attributes.modified = parse_timedate(line);
+            } else if let Some(label) = line.strip_prefix("0x00000007 <blob>=") {
+                attributes.label = parse_blob(label).filter(|label| label != service);
+            } else if let Some(comment) = line.strip_prefix("\"icmt\"<blob>=") {
+                attributes.comment = parse_blob(comment).filter(|comment| !comment.is_empty());
+            }
+        }
+        Ok(attributes)
+    }
+
+    /// `security` can only change these along with the value, so the value
+    /// is read and written back unchanged.
+    fn describe(
+        &self,
+        account: &str,
+        service: &str,
+        label: Option<&str>,
+        comment: Option<&str>,
+    ) -> Result<()> {
+        let hex = hex_encode(&self.get_bytes(account, service)?);
+        let mut args = vec![
+            "add-generic-password",
+            "-a",
+            account,
+            "-s",
+            service,
+            "-X",
+            hex.as_str(),
+            "-U",
+        ];
+        if let Some(label) = label {
+            args.extend(["-l", label]);
+        }
+        if let Some(comment) = comment {
+            args.extend(["-j", comment]);
+        }
+        self.interactive(&args, format!("failed to describe secret `{}`", service))
+    }
+
+    /// Dumps each keychain on its own, so items are attributed to the right
+    /// one and an unreadable keychain does not hide the rest.
+    fn locate(&self, service: Option<&str>) -> Result<Search> {
+        let mut search = Search::default();
+        for (keychain, searched) in keychains(self.keychain.as_deref())? {
+            let output = Command::new(SECURITY_BIN)
+                .args(["dump-keychain", &keychain])
+                .output()
+                .map_err(|error| {
+                    Error::io(format!("failed to read keychain {}", keychain), error)
+                })?;
+            if !output.status.success() {
+                search
+                    .skipped
+                    .push((keychain, security_failure(&output.stderr)));
+                continue;
+            }
+            let dump = String::from_utf8_lossy(&output.stdout);
+            search
+                .found
+                .extend(
+                    parse_locations(&dump, &keychain, service)
+                        .into_iter()
+                        .map(|location| Location {
+                            searched,
+                            ..location
+                        }),
+                );
+        }
+        Ok(search)
+    }
+
+    fn delete_copy(&self, location: &Location) -> Result<()> {
+        if location.keychain == ICLOUD_KEYCHAIN {
+            return Err(Error::Unsupported {
+                what: "iCloud Keychain with the security backend".to_string(),
+            });
+        }
+        let output = Command::new(SECURITY_BIN)
+            .args([
+                "delete-generic-password",
+                "-a",
+                &location.account,
+                "-s",
+                &location.service,
+                &location.keychain,
+            ])
+            .output()
+            .map_err(|error| {
+                Error::io(
+                    format!(
+                        "failed to delete `{}` from {}",
+                        location.service, location.keychain
+                    ),
+                    error,
+                )
+            })?;
+        if !output.status.success() {
+            if is_not_found(&output.stderr) {
+                return Err(Error::NotFound {
+                    service: location.service.clone(),
+                });
+            }
+            return Err(security_failure(&output.stderr));
+        }
+        Ok(())
+    }
+
+    fn services(&self, account: &str) -> Result<Vec<String>> {
+        let output = self.output(
+            &["dump-keychain"],
+            "failed to list keychain items".to_string(),
+        )?;
+        if !output.status.success() {
+            return Err(security_failure(&output.stderr));
+        }
+        Ok(parse_services(
+            &String::from_utf8_lossy(&output.stdout),
+            account,
+        ))
+    }
+
+    fn trusted_apps(&self, account: &str, service: &str) -> Result<TrustedApps> {
+        let output = self.output(
+            &["dump-keychain", "-a"],
+            format!("failed to read the access list of `{}`", service),
+        )?;
+        if !output.status.success() {
+            return Err(security_failure(&output.stderr));
+        }
+        parse_trusted_apps(&String::from_utf8_lossy(&output.stdout), account, service).ok_or_else(
+            || Error::NotFound {
+                service: service.to_string(),
+            },
+        )
+    }
+
+    fn get_internet(&self, item: &InternetItem) -> Result<Vec<u8>> {
+        let mut args = vec!["find-internet-password".to_string(), "-g".to_string()];
+        args.extend(Self::internet_args(item));
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let output = self.output(&args, format!("failed to read {}", item))?;
+        if !output.status.success() {
+            if is_not_found(&output.stderr) {
+                return Err(Error::NotFound {
+                    service: item.to_string(),
+                });
+            }
+            return Err(security_failure(&output.stderr));
+        }
+        String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .find_map(|line| line.strip_prefix("password:"))
+            .and_then(parse_password_line)
+            .ok_or_else(|| Error::Security {
+                message: format!("could not parse the value of {}", item),
+            })
+    }
+
+    fn set_internet(&self, item: &InternetItem, value: &[u8]) -> Result<()> {
+        let mut args = vec!["add-internet-password".to_string()];
+        args.extend(Self::internet_args(item));
+        args.extend(["-X".to_string(), hex_encode(value), "-U".to_string()]);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        self.interactive(&args, format!("failed to store {}", item))
+    }
+
+    fn delete_internet(&self, item: &InternetItem) -> Result<()> {
+        let mut args = vec!["delete-internet-password".to_string()];
+        args.extend(Self::internet_args(item));
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let output = self.output(&args, format!("failed to delete {}", item))?;
+        if output.status.success() || is_not_found(&output.stderr) {
+            return Ok(());
+        }
+        Err(security_failure(&output.stderr))
+    }
+
+    /// `security import` takes the keychain as `-k` rather than last. It
+    /// runs through `security -i` so the passphrase stays off the command
+    /// line.
+    fn import_certificates(
+        &self,
+        path: &Path,
+        passphrase: Option<&str>,
+        apps: &[String],
+    ) -> Result<()> {
+        let path_name = path.to_string_lossy();
+        let keychain = self
+            .keychain
+            .as_ref()
+            .map(|keychain| keychain.to_string_lossy());
+        let mut args = vec!["import", &path_name];
+        if let Some(keychain) = &keychain {
+            args.extend(["-k", keychain]);
+        }
+        let pkcs12 = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| matches!(extension, "p12" | "pfx"));
+        if pkcs12 {
+            args.extend(["-f", "pkcs12"]);
+        }
+        if let Some(passphrase) = passphrase {
+            args.extend(["-P", passphrase]);
+        }
+        for app in apps {
+            args.extend(["-T", app]);
+        }
+        run_interactive(&args, format!("failed to import {}", path.display()))
+    }
+
+    fn certificates(&self) -> Result<Vec<Certificate>> {
+        let output = self.output(
+            &["find-certificate", "-a", "-Z"],
+            "failed to list certificates".to_string(),
+        )?;
+        if !output.status.success() {
+            return Err(security_failure(&output.stderr));
+        }
+        let identities =
+            self.output(&["find-identity"], "failed to list identities".to_string())?;
+        if !identities.status.success() {
+            return Err(security_failure(&identities.stderr));
+        }
+        let identities = parse_identities(&String::from_utf8_lossy(&identities.stdout));
+        let mut certificates = parse_certificates(&String::from_utf8_lossy(&output.stdout));
+        for certificate in &mut certificates {
+            certificate.identity = identities.contains(&certificate.sha1);
+        }
+        Ok(certificates)
+    }
+
+    fn export_certificate(&self, sha1: &str) -> Result<String> {
+        let output = self.output(
+            &["find-certificate", "-a", "-Z", "-p"],
+            format!("failed to export certificate {}", sha1),
+        )?;
+        if !output.status.success() {
+            return Err(security_failure(&output.stderr));
+        }
+        parse_pems(&String::from_utf8_lossy(&output.stdout))
+            .into_iter()
+            .find(|(hash, _)| hash.eq_ignore_ascii_case(sha1))
+            .map(|(_, pem)| pem)
+            .ok_or_else(|| Error::NotFound {
+                service: sha1.to_string(),
+            })
+    }
+
+    /// `security` can only set an access list when creating an item, so the
+    /// item is deleted and added again with the same value. Its creation
+    /// date, label, and comment are not kept.
+    fn set_trusted_apps(&self, account: &str, service: &str, apps: &[String]) -> Result<()> {
+        let value = self.get_bytes(account, service)?;
+        let hex = hex_encode(&value);
+        let mut args = vec![
+            "add-generic-password",
+            "-a",
+            account,
+            "-s",
+            service,
+            "-X",
+            hex.as_str(),
+        ];
+        if apps.is_empty() {
+            args.extend(["-T", ""]);
+        }
+        for app in apps {
+            args.extend(["-T", app.as_str()]);
+        }
+
+        self.delete(account, service)?;
+        let result = self.interactive(
+            &args,
+            format!("failed to update the access list of `{}`", service),
+        );
+        if result.is_err() {
+            // Put the value back with the default access list rather than
+            // lose it.
+            self.set_bytes(account, service, &value)?;
+        }
+        result
+    }
+}
+
+/// Lists the keychains to search in order: the user's search list, the
+/// system keychain, other keychains in `~/Library/Keychains`, and `extra`.
+/// Each comes with whether reads look in it: with `extra`, only it is read.
+fn keychains(extra: Option<&Path>) -> Result<Vec<(String, bool)>> {
+    let mut keychains: Vec<(String, bool)> = Vec::new();
+    for domain in ["user", "system"] {
+        let output = Command::new(SECURITY_BIN)
+            .args(["list-keychains", "-d", domain])
+            .output()
+            .map_err(|error| Error::io("failed to list keychains".to_string(), error))?;
+        if !output.status.success() {
+            return Err(security_failure(&output.stderr));
+        }
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let path = line.trim().trim_matches('"');
+            if !path.is_empty() {
+                keychains.push((path.to_string(), domain == "user" && extra.is_none()));
+            }
+        }
+    }
+    if let Some(home) = std::env::var_os("HOME") {
+        let directory = Path::new(&home).join("Library/Keychains");
+        let mut files: Vec<String> = fs::read_dir(directory)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "keychain-db" || extension == "keychain")
+            })
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        keychains.extend(files.into_iter().map(|file| (file, false)));
+    }
+    if let Some(extra) = extra {
+        let extra = extra.to_string_lossy().into_owned();
+        keychains.retain(|(keychain, _)| *keychain != extra);
+        keychains.push((extra, true));
+    }
+    let mut seen = std::collections::HashSet::new();
+    keychains.retain(|(keychain, _)| seen.insert(keychain.clone()));
+    Ok(keychains)
+}
+
+/// Collects the generic passwords for `service`, or all of them, in the
+/// `dump-keychain` output of `keychain`.
+fn parse_locations(dump: &str, keychain: &str, service: Option<&str>) -> Vec<Location> {
+    let mut locations = Vec::new();
+    for item in dump.split("keychain: ").skip(1) {
+        let mut generic = false;
+        let mut location = Location {
+            keychain: keychain.to_string(),
+            account: String::new(),
+            service: String::new(),
+            modified: None,
+            searched: false,
+        };
+        for line in item.lines().map(str::trim) {
+            if line == "class: \"genp\"" {
+                generic = true;
+            } else if let Some(value) = line.strip_prefix("\"acct\"<blob>=") {
+                location.account = parse_blob(value).unwrap_or_default();
+            } else if let Some(value) = line.strip_prefix("\"svce\"<blob>=") {
+                location.service = parse_blob(value).unwrap_or_default();
+            } else if line.starts_with("\"mdat\"<timedate>=") {
+                location.modified = parse_timedate(line);
+            }
+        }
+        if generic && service.is_none_or(|service| location.service == service) {
+            locations.push(location);
+        }
+    }
+    locations
+}
+
+/// Finds the generic password item for `account` and `service` in
+/// `security dump-keychain -a` output and reads the applications listed on
+/// its `decrypt` access entry. Returns `None` if the item is not in the dump.
+fn parse_trusted_apps(dump: &str, account: &str, service: &str) -> Option<TrustedApps> {
+    let account_line = format!("\"acct\"<blob>=\"{}\"", account);
+    let service_line = format!("\"svce\"<blob>=\"{}\"", service);
+    for item in dump.split("keychain: ").skip(1) {
+        let lines: Vec<&str> = item.lines().map(str::trim).collect();
+        if !lines.contains(&"class: \"genp\"")
+            || !lines.contains(&account_line.as_str())
+            || !lines.contains(&service_line.as_str())
+        {
+            continue;
+        }
+
+        let mut decrypt = false;
+        let mut apps: Option<Vec<String>> = None;
+        for line in lines {
+            if line.starts_with("entry ") {
+                if apps.is_some() {
+                    break;
+                }
+                decrypt = false;
+            } else if let Some(authorizations) = line.strip_prefix("authorizations") {
+                decrypt = authorizations
+                    .split_whitespace()
+                    .any(|name| name == "decrypt");
+            } else if decrypt && line.starts_with("applications") {
+                if line.ends_with("<null>") {
+                    return Some(TrustedApps::Any);
+                }
+                apps = Some(Vec::new());
+            } else if let Some(apps) = apps.as_mut()
+                && let Some((index, app)) = line.split_once(": ")
+                && index.parse::<usize>().is_ok()
+            {
+                // Drop the status suffix, e.g. `/usr/bin/ssh (OK)`.
+                let app = app.rsplit_once(" (").map_or(app, |(path, _)| path);
+                apps.push(app.to_string());
+            }
+        }
+        return Some(TrustedApps::Only(apps.unwrap_or_default()));
+    }
+    None
+}
+
+/// Collects the services of the generic passwords for `account` in
+/// `dump-keychain` output.
+fn parse_services(dump: &str, account: &str) -> Vec<String> {
+    let mut services = Vec::new();
+    for item in dump.split("keychain: ").skip(1) {
+        let mut generic = false;
+        let mut item_account = None;
+        let mut service = None;
+        for line in item.lines().map(str::trim) {
+            if line == "class: \"genp\"" {
+                generic = true;
+            } else if let Some(value) = line.strip_prefix("\"acct\"<blob>=") {
+                item_account = parse_password_line(value);
+            } else if let Some(value) = line.strip_prefix("\"svce\"<blob>=") {
+                service = parse_password_line(value);
+            }
+        }
+        if generic
+            && item_account.as_deref() == Some(account.as_bytes())
+            && let Some(service) = service
+        {
+            services.push(String::from_utf8_lossy(&service).into_owned());
+        }
+    }
+    services
+}
+
+/// Parses `find-certificate -a -Z` output, where each certificate starts
+/// with its fingerprints and lists its label among the attributes.
+fn parse_certificates(output: &str) -> Vec<Certificate> {
+    let mut certificates: Vec<Certificate> = Vec::new();
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(sha1) = line.strip_prefix("SHA-1 hash:") {
+            certificates.push(Certificate {
+                name: String::new(),
+                sha1: sha1.trim().to_ascii_uppercase(),
+                identity: false,
+            });
+            continue;
+        }
+        let Some(certificate) = certificates.last_mut() else {
+            continue;
+        };
+        let label = line.strip_prefix("\"labl\"<blob>=").or_else(|| {
+            line.strip_prefix("\"alis\"<blob>=")
+                .filter(|_| certificate.name.is_empty())
+        });
+        if let Some(name) = label.and_then(parse_password_line) {
+            certificate.name = String::from_utf8_lossy(&name).into_owned();
+        }
+    }
+    certificates
+}
+
+/// Collects the fingerprints in `find-identity` output, whose lines look
+/// like `  1) 0123ABCD... "Apple Development: ..."`.
+fn parse_identities(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (number, rest) = line.trim().split_once(") ")?;
+            number.parse::<u32>().ok()?;
+            let hash = rest.split_whitespace().next()?;
+            Some(hash.to_ascii_uppercase())
+        })
+        .collect()
+}
+
+/// Pairs each SHA-1 fingerprint in `find-certificate -a -Z -p` output with
+/// the PEM block that follows it.
+fn parse_pems(output: &str) -> Vec<(String, String)> {
+    let mut pems: Vec<(String, String)> = Vec::new();
+    let mut inside = false;
+    for line in output.lines() {
+        if let Some(sha1) = line.trim().strip_prefix("SHA-1 hash:") {
+            pems.push((sha1.trim().to_string(), String::new()));
+            continue;
+        }
+        let Some((_, pem)) = pems.last_mut() else {
+            continue;
+        };
+        if line.starts_with("-----BEGIN ") {
+            inside = true;
+        }
+        if inside {
+            pem.push_str(line);
+            pem.push('\n');
+        }
+        if line.starts_with("-----END ") {
+            inside = false;
+        }
+    }
+    pems
+}
+
+/// Converts a `security` timedate attribute such as
+/// `"mdat"<timedate>=0x3230...  "20240102030405Z\000"` into RFC 3339.
+fn parse_timedate(line: &str) -> Option<String> {
+    let start = line.find("  \"")? + 3;
+    let raw = line[start..].get(..14)?;
+    if !raw.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &raw[0..4],
+        &raw[4..6],
+        &raw[6..8],
+        &raw[8..10],
+        &raw[10..12],
+        &raw[12..14]
+    ))
+}
+
+/// Parses a text attribute such as `"my label"`, which is `<NULL>` when
+/// unset.
+fn parse_blob(value: &str) -> Option<String> {
+    let bytes = parse_password_line(value)?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Decodes the value from a `security -g` password line, which is either
+/// `"text"` or `0x<hex>  "escaped text"`.
+fn parse_password_line(value: &str) -> Option<Vec<u8>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Some(Vec::new());
+    }
+    if let Some(hex) = value.strip_prefix("0x") {
+        let hex = hex.split_whitespace().next().unwrap_or("");
+        return hex_decode(hex);
+    }
+    let text = value.strip_prefix('"')?.strip_suffix('"')?;
+    Some(text.as_bytes().to_vec())
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interactive_quote_wraps_plain_words() {
+        assert_eq!(interactive_quote("github_token"), r#""github_token""#);
+        assert_eq!(interactive_quote(""), r#""""#);
+    }
+
+    #[test]
+    fn interactive_quote_escapes_quotes_and_backslashes() {
+        assert_eq!(interactive_quote(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(interactive_quote(r"C:\keys\"), r#""C:\\keys\\""#);
+        assert_eq!(interactive_quote(r#"\""#), r#""\\\"""#);
+    }
+
+    #[test]
+    fn interactive_quote_keeps_spaces_and_non_ascii() {
+        assert_eq!(
+            interactive_quote("/Applications/My App.app"),
+            r#""/Applications/My App.app""#
+        );
+        assert_eq!(interactive_quote("café ✓ 鍵"), r#""café ✓ 鍵""#);
+    }
+
+    #[test]
+    fn interactive_rejects_line_breaks() {
+        for name in ["two\nlines", "carriage\rreturn"] {
+            let result = run_interactive(
+                &["add-generic-password", "-s", name],
+                "failed to save".to_string(),
+            );
+            assert!(
+                matches!(&result, Err(Error::Security { message }) if message.contains("line breaks")),
+                "{:?}",
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn hex_values_survive_interactive_quoting() {
+        let values: [&[u8]; 6] = [
+            br#"say "hi""#,
+            br"C:\keys\",
+            b"two words",
+            "café ✓ 鍵".as_bytes(),
+            b"-----BEGIN KEY-----\nabc\n-----END KEY-----\n",
+            b"\0\x7f\xff",
+        ];
+        for value in values {
+            let hex = hex_encode(value);
+            assert!(hex.bytes().all(|byte| byte.is_ascii_hexdigit()));
+            assert_eq!(interactive_quote(&hex), format!("\"{}\"", hex));
+            assert_eq!(hex_decode(&hex).as_deref(), Some(value));
+            let line = format!("0x{}  \"escaped\"", hex);
+            assert_eq!(parse_password_line(&line).as_deref(), Some(value));
+        }
+    }
+
+    #[test]
+    fn hex_decode_rejects_malformed_input() {
+        assert_eq!(hex_decode("ABC"), None);
+        assert_eq!(hex_decode("ZZ"), None);
+        assert_eq!(hex_decode(""), Some(Vec::new()));
+    }
+}
@@ -0,0 +1,61 @@
+//! Touch ID prompts through LocalAuthentication.
+
+use crate::{Error, Result};
+
+/// Asks the user to confirm with Touch ID. `reason` completes the sentence
+/// "keychainctl is trying to ...", e.g. "read the secret `github_token`".
+///
+/// Fails when biometrics are unavailable, so a policy that requires them
+/// never silently falls back to no check at all.
+#[cfg(target_os = "macos")]
+pub fn authenticate(reason: &str) -> Result<()> {
+    use std::sync::mpsc;
+
+    use block2::RcBlock;
+    use objc2::runtime::Bool;
+    use objc2_foundation::{NSError, NSString};
+    use objc2_local_authentication::{LAContext, LAPolicy};
+
+    let policy = LAPolicy::DeviceOwnerAuthenticationWithBiometrics;
+    let context = unsafe { LAContext::new() };
+    if let Err(error) = unsafe { context.canEvaluatePolicy_error(policy) } {
+        return Err(Error::AuthenticationFailed {
+            message: error.localizedDescription().to_string(),
+        });
+    }
+
+    // The reply arrives on a LocalAuthentication queue; wait for it here.
+    let (sender, receiver) = mpsc::channel();
+    let reply = RcBlock::new(move |success: Bool, error: *mut NSError| {
+        let outcome = if success.as_bool() {
+            Ok(())
+        } else {
+            // SAFETY: LocalAuthentication passes either null or a valid
+            // NSError that outlives the reply block.
+            let message = unsafe { error.as_ref() }
+                .map(|error| error.localizedDescription().to_string())
+                .unwrap_or_else(|| "not confirmed".to_string());
+            Err(message)
+        };
+        let _ = sender.send(outcome);
+    });
+    unsafe {
+        context.evaluatePolicy_localizedReason_reply(policy, &NSString::from_str(reason), &reply);
+    }
+
+    match receiver.recv() {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(message)) => Err(Error::AuthenticationFailed { message }),
+        Err(_) => Err(Error::AuthenticationFailed {
+            message: "no response from LocalAuthentication".to_string(),
+        }),
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn authenticate(reason: &str) -> Result<()> {
+    let _ = reason;
+    Err(Error::Unsupported {
+        what: "biometric authentication".to_string(),
+    })
+}
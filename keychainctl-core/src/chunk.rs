@@ -0,0 +1,243 @@
+//! Values too large for one keychain item, split across several.
+//!
+//! A value longer than [`SIZE`] is stored in pieces under `github_token#1`,
+//! `github_token#2`, and so on, while `github_token` itself holds only a
+//! marker with the number of pieces. Reads put the pieces back together,
+//! and listings leave them out, so callers never see them.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::backend::{
+    Backend, Certificate, InternetItem, ItemAttributes, Location, Search, TrustedApps,
+};
+use crate::{Error, Result};
+
+/// The largest value stored in a single item. Kept small enough that the
+/// hex-encoded value fits on one `security -i` command line.
+pub const SIZE: usize = 1536;
+
+/// Starts the marker stored in place of a chunked value. The leading NUL
+/// keeps it from matching any text secret.
+const MARKER: &[u8] = b"\0keychainctl-chunks:";
+
+/// Returns the service name holding piece `index` (from 1) of `service`.
+pub fn item(service: &str, index: usize) -> String {
+    format!("{}#{}", service, index)
+}
+
+/// Wraps a keychain backend to chunk large values.
+pub struct Chunked {
+    inner: Box<dyn Backend>,
+}
+
+impl Chunked {
+    pub fn new(inner: Box<dyn Backend>) -> Self {
+        Self { inner }
+    }
+
+    /// The pieces stored for `service`, found without reading any value.
+    fn pieces(&self, account: &str, service: &str) -> Result<Vec<String>> {
+        let mut pieces = Vec::new();
+        while self
+            .inner
+            .exists(account, &item(service, pieces.len() + 1))?
+        {
+            pieces.push(item(service, pieces.len() + 1));
+        }
+        Ok(pieces)
+    }
+
+    /// Deletes the pieces of `service` from `first` on.
+    fn delete_pieces(&self, account: &str, service: &str, first: usize) -> Result<()> {
+        for piece in self.pieces(account, service)?.iter().skip(first - 1) {
+            self.inner.delete(account, piece)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the service `service` would be a piece of, going by its name.
+fn piece_of(service: &str) -> Option<&str> {
+    let (base, index) = service.rsplit_once('#')?;
+    index.parse::<usize>().is_ok().then_some(base)
+}
+
+/// Returns the number of pieces a marker announces, or `None` for an
+/// ordinary value.
+fn parse_marker(value: &[u8]) -> Option<usize> {
+    let count = value.strip_prefix(MARKER)?;
+    std::str::from_utf8(count).ok()?.parse().ok()
+}
+
+impl Backend for Chunked {
+    fn get_bytes(&self, account: &str, service: &str) -> Result<Vec<u8>> {
+        let value = self.inner.get_bytes(account, service)?;
+        let Some(count) = parse_marker(&value) else {
+            return Ok(value);
+        };
+        let mut joined = Vec::new();
+        for index in 1..=count {
+            joined.extend(self.inner.get_bytes(account, &item(service, index))?);
+        }
+        Ok(joined)
+    }
+
+    /// Writes the pieces before the marker, so a failed write leaves the
+    /// previous value readable.
+    fn set_bytes(&self, account: &str, service: &str, value: &[u8]) -> Result<()> {
+        if value.len() <= SIZE {
+            self.inner.set_bytes(account, service, value)?;
+            return self.delete_pieces(account, service, 1);
+        }
+        let pieces: Vec<&[u8]> = value.chunks(SIZE).collect();
+        for (index, piece) in pieces.iter().enumerate() {
+            self.inner
+                .set_bytes(account, &item(service, index + 1), piece)?;
+        }
+        let mut marker = MARKER.to_vec();
+        marker.extend(pieces.len().to_string().as_bytes());
+        self.inner.set_bytes(account, service, &marker)?;
+        self.delete_pieces(account, service, pieces.len() + 1)
+    }
+
+    fn delete(&self, account: &str, service: &str) -> Result<()> {
+        self.inner.delete(account, service)?;
+        self.delete_pieces(account, service, 1)
+    }
+
+    fn exists(&self, account: &str, service: &str) -> Result<bool> {
+        self.inner.exists(account, service)
+    }
+
+    fn attributes(&self, account: &str, service: &str) -> Result<ItemAttributes> {
+        self.inner.attributes(account, service)
+    }
+
+    /// Leaves out the pieces of chunked values.
+    fn services(&self, account: &str) -> Result<Vec<String>> {
+        let services = self.inner.services(account)?;
+        let names: BTreeSet<&str> = services.iter().map(String::as_str).collect();
+        Ok(services
+            .iter()
+            .filter(|service| piece_of(service).is_none_or(|base| !names.contains(base)))
+            .cloned()
+            .collect())
+    }
+
+    fn trusted_apps(&self, account: &str, service: &str) -> Result<TrustedApps> {
+        self.inner.trusted_apps(account, service)
+    }
+
+    /// Changes the access list of every piece too, so that reading a
+    /// chunked value asks no more often than reading any other.
+    fn set_trusted_apps(&self, account: &str, service: &str, apps: &[String]) -> Result<()> {
+        for piece in self.pieces(account, service)? {
+            self.inner.set_trusted_apps(account, &piece, apps)?;
+        }
+        self.inner.set_trusted_apps(account, service, apps)
+    }
+
+    fn get_internet(&self, item: &InternetItem) -> Result<Vec<u8>> {
+        self.inner.get_internet(item)
+    }
+
+    fn set_internet(&self, item: &InternetItem, value: &[u8]) -> Result<()> {
+        self.inner.set_internet(item, value)
+    }
+
+    fn delete_internet(&self, item: &InternetItem) -> Result<()> {
+        self.inner.delete_internet(item)
+    }
+
+    fn import_certificates(
+        &self,
+        path: &Path,
+        passphrase: Option<&str>,
+        apps: &[String],
+    ) -> Result<()> {
+        self.inner.import_certificates(path, passphrase, apps)
+    }
+
+    fn certificates(&self) -> Result<Vec<Certificate>> {
+        self.inner.certificates()
+    }
+
+    fn export_certificate(&self, sha1: &str) -> Result<String> {
+        self.inner.export_certificate(sha1)
+    }
+
+    fn describe(
+        &self,
+        account: &str,
+        service: &str,
+        label: Option<&str>,
+        comment: Option<&str>,
+    ) -> Result<()> {
+        self.inner.describe(account, service, label, comment)
+    }
+
+    fn synchronize(&self, account: &str, service: &str) -> Result<()> {
+        for piece in self.pieces(account, service)? {
+            self.inner.synchronize(account, &piece)?;
+        }
+        self.inner.synchronize(account, service)
+    }
+
+    /// Leaves out the pieces of chunked values, as `services` does, for
+    /// each keychain and account.
+    fn locate(&self, service: Option<&str>) -> Result<Search> {
+        let mut search = self.inner.locate(service)?;
+        let copies: BTreeSet<(String, String, String)> = search
+            .found
+            .iter()
+            .map(|copy| {
+                (
+                    copy.keychain.clone(),
+                    copy.account.clone(),
+                    copy.service.clone(),
+                )
+            })
+            .collect();
+        search.found.retain(|copy| {
+            piece_of(&copy.service).is_none_or(|base| {
+                !copies.contains(&(
+                    copy.keychain.clone(),
+                    copy.account.clone(),
+                    base.to_string(),
+                ))
+            })
+        });
+        Ok(search)
+    }
+
+    /// Deletes the pieces stored next to that copy too.
+    fn delete_copy(&self, location: &Location) -> Result<()> {
+        self.inner.delete_copy(location)?;
+        for index in 1.. {
+            let piece = Location {
+                keychain: location.keychain.clone(),
+                account: location.account.clone(),
+                service: item(&location.service, index),
+                modified: None,
+                searched: location.searched,
+            };
+            match self.inner.delete_copy(&piece) {
+                Ok(()) => {}
+                Err(Error::NotFound { .. }) => break,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    fn rename(&self, account: &str, from: &str, to: &str) -> Result<()> {
+        let pieces = self.pieces(account, from)?;
+        self.inner.rename(account, from, to)?;
+        for index in 1..=pieces.len() {
+            self.inner
+                .rename(account, &item(from, index), &item(to, index))?;
+        }
+        Ok(())
+    }
+}
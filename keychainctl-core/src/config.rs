@@ -0,0 +1,467 @@
+//! The config directory and `config.toml`.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+use crate::{Error, Result, date, history, strength};
+
+/// Top-level keys that `config set` can change.
+pub const KEYS: [&str; 9] = [
+    "account",
+    "keychain",
+    "output",
+    "confirm",
+    "clipboard-timeout",
+    "mask-terminal",
+    "min-strength",
+    "history",
+    "registry",
+];
+
+/// Returns `$XDG_CONFIG_HOME/keychainctl`, falling back to
+/// `~/.config/keychainctl`.
+pub fn dir() -> Result<PathBuf> {
+    if let Ok(dir) = env::var("XDG_CONFIG_HOME")
+        && !dir.trim().is_empty()
+    {
+        return Ok(Path::new(&dir).join("keychainctl"));
+    }
+    let home = env::var("HOME").map_err(|_| Error::HomeNotSet)?;
+    Ok(Path::new(&home).join(".config/keychainctl"))
+}
+
+/// Settings read from `config.toml`. Every field is optional, and command
+/// line flags and environment variables take precedence.
+///
+/// ```toml
+/// history = 10
+/// account = "octocat"
+/// output = "json"
+/// confirm = false
+/// clipboard-timeout = "1m"
+/// registry = "keychain"
+///
+/// [hooks]
+/// post-set = "make -C ~/dev restart-api"
+///
+/// [namespaces."prod"]
+/// min-strength = 4
+///
+/// [services."github_token"]
+/// post-rotate = "gh secret set GITHUB_TOKEN"
+/// require-biometrics = true
+/// auth-header = "Authorization: token {value}"
+///
+/// [services."db-password"]
+/// url = "postgres://app@db.internal:5432/app?sslmode=require"
+///
+/// [providers."github"]
+/// client-id = "Iv1.0123456789abcdef"
+/// scope = "repo read:org"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// How many earlier values to keep per secret (0 disables history).
+    pub history: Option<usize>,
+    /// Account used when `--account` is not given, instead of `$USER`.
+    pub account: Option<String>,
+    /// Keychain file used when `--keychain` is not given. A leading `~/`
+    /// stands for `$HOME`.
+    pub keychain: Option<PathBuf>,
+    /// Default output format.
+    pub output: Option<Output>,
+    /// Ask before deleting or pushing secrets (true when unset).
+    pub confirm: Option<bool>,
+    /// How long `get --clipboard` keeps the value, such as `30s` or `2m`.
+    pub clipboard_timeout: Option<String>,
+    /// Mask values that `get` prints to a terminal (true when unset).
+    pub mask_terminal: Option<bool>,
+    /// Lowest strength score, from 0 to 4, that `set` and `generate`
+    /// accept.
+    pub min_strength: Option<u8>,
+    /// Where the registry of tracked services is kept.
+    pub registry: Option<RegistryStore>,
+    /// Commands run around every change to a secret.
+    pub hooks: Hooks,
+    /// Per-namespace settings keyed by the namespace, such as `prod` for
+    /// `prod/db-password`.
+    pub namespaces: BTreeMap<String, NamespaceConfig>,
+    /// Per-service settings keyed by the full service name.
+    pub services: BTreeMap<String, ServiceConfig>,
+    /// OAuth providers `login` can sign in to, keyed by name.
+    pub providers: BTreeMap<String, Provider>,
+}
+
+/// An OAuth 2.0 provider that supports the device authorization grant of
+/// RFC 8628. The endpoints of [`KNOWN_PROVIDERS`] need not be given.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Provider {
+    /// Where device and user codes are requested.
+    pub device_url: Option<String>,
+    /// Where tokens are requested and refreshed.
+    pub token_url: Option<String>,
+    pub client_id: Option<String>,
+    /// Only for providers that want one from native apps, where it is not
+    /// actually secret.
+    pub client_secret: Option<String>,
+    /// Space-separated scopes to ask for.
+    pub scope: Option<String>,
+}
+
+/// Providers whose endpoints are built in, as name, device code URL, and
+/// token URL. Each still needs a `client-id` of an OAuth app.
+pub const KNOWN_PROVIDERS: [(&str, &str, &str); 3] = [
+    (
+        "github",
+        "https://github.com/login/device/code",
+        "https://github.com/login/oauth/access_token",
+    ),
+    (
+        "google",
+        "https://oauth2.googleapis.com/device/code",
+        "https://oauth2.googleapis.com/token",
+    ),
+    (
+        "microsoft",
+        "https://login.microsoftonline.com/common/oauth2/v2.0/devicecode",
+        "https://login.microsoftonline.com/common/oauth2/v2.0/token",
+    ),
+];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct NamespaceConfig {
+    /// Overrides the top-level `min-strength` for secrets in the namespace.
+    pub min_strength: Option<u8>,
+}
+
+/// Shell commands run before and after secrets are written or deleted,
+/// with the service and account in `KEYCHAINCTL_SERVICE` and
+/// `KEYCHAINCTL_ACCOUNT`. A failing `pre-` hook stops the change.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Hooks {
+    pub pre_set: Option<String>,
+    pub post_set: Option<String>,
+    pub pre_delete: Option<String>,
+    pub post_delete: Option<String>,
+}
+
+impl Hooks {
+    /// Returns the command for `event`, such as `post-set`.
+    pub fn get(&self, event: &str) -> Option<&str> {
+        match event {
+            "pre-set" => self.pre_set.as_deref(),
+            "post-set" => self.post_set.as_deref(),
+            "pre-delete" => self.pre_delete.as_deref(),
+            "post-delete" => self.post_delete.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// Output formats that `output` can select.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Output {
+    Text,
+    Json,
+}
+
+/// Places the registry can be kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegistryStore {
+    /// `registry.db` in the config directory.
+    #[default]
+    File,
+    /// A single item in the keychain, so the list of services is protected
+    /// like the secrets themselves.
+    Keychain,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ServiceConfig {
+    /// Shell command run after `rotate` stores a new value.
+    pub post_rotate: Option<String>,
+    /// Ask for Touch ID before the value is read or the secret deleted.
+    pub require_biometrics: bool,
+    /// Header `keychainctl curl` sends the value in, with `{value}`
+    /// standing for it, such as `PRIVATE-TOKEN: {value}`.
+    pub auth_header: Option<String>,
+    /// Connection URL `keychainctl url` puts the value into, as the
+    /// password of its user or wherever `{value}` appears.
+    pub url: Option<String>,
+}
+
+impl Config {
+    pub fn history_depth(&self) -> usize {
+        self.history.unwrap_or(history::DEFAULT_DEPTH)
+    }
+
+    /// Returns `keychain` with a leading `~/` expanded.
+    pub fn keychain(&self) -> Option<PathBuf> {
+        let path = self.keychain.as_ref()?;
+        match (path.strip_prefix("~"), env::var_os("HOME")) {
+            (Ok(rest), Some(home)) => Some(Path::new(&home).join(rest)),
+            _ => Some(path.clone()),
+        }
+    }
+
+    pub fn confirm(&self) -> bool {
+        self.confirm.unwrap_or(true)
+    }
+
+    pub fn mask_terminal(&self) -> bool {
+        self.mask_terminal.unwrap_or(true)
+    }
+
+    pub fn registry_store(&self) -> RegistryStore {
+        self.registry.unwrap_or_default()
+    }
+
+    /// Returns `clipboard-timeout` in seconds, if set.
+    pub fn clipboard_timeout(&self) -> Result<Option<u64>> {
+        self.clipboard_timeout
+            .as_deref()
+            .map(date::parse_seconds)
+            .transpose()
+    }
+
+    /// Returns the value of a top-level key as it would be written in
+    /// `config.toml`, or `None` when it is unset.
+    pub fn get(&self, key: &str) -> Option<String> {
+        match key {
+            "history" => self.history.map(|depth| depth.to_string()),
+            "account" => self.account.clone(),
+            "keychain" => self
+                .keychain
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            "output" => self.output.map(|output| match output {
+                Output::Text => "text".to_string(),
+                Output::Json => "json".to_string(),
+            }),
+            "confirm" => self.confirm.map(|confirm| confirm.to_string()),
+            "clipboard-timeout" => self.clipboard_timeout.clone(),
+            "mask-terminal" => self.mask_terminal.map(|mask| mask.to_string()),
+            "min-strength" => self.min_strength.map(|score| score.to_string()),
+            "registry" => self.registry.map(|store| match store {
+                RegistryStore::File => "file".to_string(),
+                RegistryStore::Keychain => "keychain".to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn service(&self, service: &str) -> Option<&ServiceConfig> {
+        self.services.get(service)
+    }
+
+    /// Returns the lowest strength score accepted for `service`, from the
+    /// innermost namespace that sets one or else the top level.
+    pub fn min_strength(&self, service: &str) -> Option<u8> {
+        self.namespaces
+            .iter()
+            .filter(|(namespace, _)| {
+                service
+                    .strip_prefix(namespace.trim_end_matches('/'))
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .filter_map(|(namespace, settings)| Some((namespace.len(), settings.min_strength?)))
+            .max_by_key(|(length, _)| *length)
+            .map(|(_, score)| score)
+            .or(self.min_strength)
+            .map(|score| score.min(strength::MAX))
+    }
+
+    pub fn requires_biometrics(&self, service: &str) -> bool {
+        self.service(service)
+            .is_some_and(|settings| settings.require_biometrics)
+    }
+
+    /// Returns the provider `name`, with the endpoints of a known provider
+    /// filled in where `config.toml` leaves them out.
+    pub fn provider(&self, name: &str) -> Option<Provider> {
+        let known = KNOWN_PROVIDERS.iter().find(|(known, ..)| *known == name);
+        let mut provider = match (self.providers.get(name), known) {
+            (Some(provider), _) => provider.clone(),
+            (None, Some(_)) => Provider::default(),
+            (None, None) => return None,
+        };
+        if let Some((_, device_url, token_url)) = known {
+            provider
+                .device_url
+                .get_or_insert_with(|| device_url.to_string());
+            provider
+                .token_url
+                .get_or_insert_with(|| token_url.to_string());
+        }
+        Some(provider)
+    }
+
+    /// Returns the header template for `service`, a bearer token unless
+    /// `auth-header` says otherwise.
+    pub fn auth_header(&self, service: &str) -> &str {
+        self.service(service)
+            .and_then(|settings| settings.auth_header.as_deref())
+            .unwrap_or(DEFAULT_AUTH_HEADER)
+    }
+}
+
+/// The header `keychainctl curl` sends when `auth-header` is not set.
+pub const DEFAULT_AUTH_HEADER: &str = "Authorization: Bearer {value}";
+
+pub fn path() -> Result<PathBuf> {
+    Ok(dir()?.join("config.toml"))
+}
+
+/// Loads `config.toml`, returning the defaults when it does not exist.
+pub fn load() -> Result<Config> {
+    let path = path()?;
+    parse(&path, &read(&path)?)
+}
+
+/// Sets a top-level key, keeping the rest of `config.toml` and its
+/// comments as they are.
+pub fn set(key: &str, value: &str) -> Result<()> {
+    let path = path()?;
+    let invalid = |message: String| Error::InvalidConfig {
+        path: path.clone(),
+        message: format!("`{}`: {}", key, message),
+    };
+    let value = match key {
+        "history" => value
+            .parse::<usize>()
+            .map_err(|_| invalid(format!("expected a number, got `{}`", value)))?
+            .to_string(),
+        "confirm" | "mask-terminal" => value
+            .parse::<bool>()
+            .map_err(|_| invalid(format!("expected true or false, got `{}`", value)))?
+            .to_string(),
+        "min-strength" => value
+            .parse::<u8>()
+            .ok()
+            .filter(|score| *score <= strength::MAX)
+            .ok_or_else(|| invalid(format!("expected a score from 0 to 4, got `{}`", value)))?
+            .to_string(),
+        "output" if value != "text" && value != "json" => {
+            return Err(invalid(format!("expected text or json, got `{}`", value)));
+        }
+        "registry" if value != "file" && value != "keychain" => {
+            return Err(invalid(format!(
+                "expected file or keychain, got `{}`",
+                value
+            )));
+        }
+        "clipboard-timeout" => {
+            date::parse_seconds(value)?;
+            toml::Value::from(value).to_string()
+        }
+        _ => toml::Value::from(value).to_string(),
+    };
+    edit(&path, key, Some(&format!("{} = {}", key, value)))
+}
+
+/// Removes a top-level key from `config.toml`.
+pub fn unset(key: &str) -> Result<()> {
+    edit(&path()?, key, None)
+}
+
+/// Replaces the line setting `key` with `line`, or deletes it when `line`
+/// is `None`. New keys go before the first table so they stay top-level.
+fn edit(path: &Path, key: &str, line: Option<&str>) -> Result<()> {
+    if !KEYS.contains(&key) {
+        return Err(Error::InvalidConfig {
+            path: path.to_path_buf(),
+            message: format!("unknown key `{}`", key),
+        });
+    }
+    let data = read(path)?;
+    let mut lines: Vec<String> = data.lines().map(str::to_string).collect();
+    let tables = lines
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .unwrap_or(lines.len());
+    let existing = lines[..tables].iter().position(|line| {
+        line.trim_start()
+            .strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start().starts_with('='))
+    });
+    match (existing, line) {
+        (Some(index), Some(line)) => lines[index] = line.to_string(),
+        (Some(index), None) => {
+            lines.remove(index);
+            let blank = |index: usize| lines.get(index).is_some_and(|line| line.trim().is_empty());
+            if index > 0 && blank(index - 1) && blank(index) {
+                lines.remove(index);
+            }
+        }
+        (None, Some(line)) => {
+            // Go after the last top-level line, so comments and blank lines
+            // above the first table stay with it.
+            let mut index = tables;
+            while index > 0 {
+                let previous = lines[index - 1].trim_start();
+                if !previous.is_empty() && !previous.starts_with('#') {
+                    break;
+                }
+                index -= 1;
+            }
+            lines.insert(index, line.to_string());
+            if index == 0 && lines.get(1).is_some_and(|next| !next.trim().is_empty()) {
+                lines.insert(1, String::new());
+            }
+        }
+        (None, None) => return Ok(()),
+    }
+
+    let mut data = lines.join("\n");
+    data.push('\n');
+    parse::<Config>(path, &data)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| Error::io(format!("failed to create {}", parent.display()), error))?;
+    }
+    fs::write(path, data)
+        .map_err(|error| Error::io(format!("failed to write {}", path.display()), error))
+}
+
+/// Reads `path`, treating a missing file as empty.
+fn read(path: &Path) -> Result<String> {
+    match fs::read_to_string(path) {
+        Ok(data) => Ok(data),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(error) => Err(Error::io(
+            format!("failed to read {}", path.display()),
+            error,
+        )),
+    }
+}
+
+/// Parses a TOML file, reporting errors with their line number.
+pub(crate) fn parse<T: DeserializeOwned>(path: &Path, data: &str) -> Result<T> {
+    toml::from_str(data).map_err(|error| {
+        let message = match error.span() {
+            Some(span) => format!(
+                "line {}: {}",
+                data[..span.start].matches('\n').count() + 1,
+                error.message()
+            ),
+            None => error.message().to_string(),
+        };
+        Error::InvalidConfig {
+            path: path.to_path_buf(),
+            message,
+        }
+    })
+}
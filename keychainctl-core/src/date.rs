@@ -0,0 +1,176 @@
+//! Calendar dates (UTC) and durations such as `90d` or `30s`.
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Error, Result};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// A proleptic Gregorian date, ordered chronologically.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    /// Parses `YYYY-MM-DD`.
+    pub fn parse(value: &str) -> Result<Self> {
+        let invalid = || Error::InvalidDate {
+            value: value.to_string(),
+        };
+        let mut parts = value.trim().splitn(3, '-');
+        let (Some(year), Some(month), Some(day)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+            return Err(invalid());
+        }
+        let year: i32 = year.parse().map_err(|_| invalid())?;
+        let month: u32 = month.parse().map_err(|_| invalid())?;
+        let day: u32 = day.parse().map_err(|_| invalid())?;
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return Err(invalid());
+        }
+        Ok(Self { year, month, day })
+    }
+
+    /// Returns the current date in UTC.
+    pub fn today() -> Self {
+        Self::from_unix(now())
+    }
+
+    /// Returns the UTC date of a Unix timestamp.
+    pub fn from_unix(seconds: u64) -> Self {
+        Self::from_days((seconds / SECONDS_PER_DAY) as i64)
+    }
+
+    pub fn add_days(self, days: i64) -> Self {
+        Self::from_days(self.to_days() + days)
+    }
+
+    /// Returns how many days lie between `self` and `later` (negative when
+    /// `later` is earlier).
+    pub fn days_until(self, later: Self) -> i64 {
+        later.to_days() - self.to_days()
+    }
+
+    /// Days since 1970-01-01, using the algorithm from Howard Hinnant's
+    /// `chrono`-compatible date library.
+    fn to_days(self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = i64::from(self.month);
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    fn from_days(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        } as u32;
+        let year = (year_of_era + era * 400 + i64::from(month <= 2)) as i32;
+        Self { year, month, day }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "{:04}-{:02}-{:02}",
+            self.year, self.month, self.day
+        )
+    }
+}
+
+/// Returns the current time in UTC as RFC 3339, e.g.
+/// `2024-01-02T03:04:05Z`.
+pub fn timestamp() -> String {
+    timestamp_ago(0)
+}
+
+/// Like [`timestamp`], for `seconds` ago.
+pub fn timestamp_ago(seconds: u64) -> String {
+    format_unix(now().saturating_sub(seconds))
+}
+
+/// Formats a Unix timestamp like [`timestamp`].
+pub fn format_unix(seconds: u64) -> String {
+    let time = seconds % SECONDS_PER_DAY;
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        Date::from_unix(seconds),
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Parses a duration in days: `14d`, `2w`, or a bare number of days.
+pub fn parse_days(value: &str) -> Result<i64> {
+    let invalid = || Error::InvalidDuration {
+        value: value.to_string(),
+    };
+    let trimmed = value.trim();
+    let (number, unit) = match trimmed.strip_suffix(['d', 'w']) {
+        Some(number) => (number, &trimmed[number.len()..]),
+        None => (trimmed, "d"),
+    };
+    let number: i64 = number.parse().map_err(|_| invalid())?;
+    if number < 0 {
+        return Err(invalid());
+    }
+    Ok(if unit == "w" { number * 7 } else { number })
+}
+
+/// Parses a duration in seconds: `30s`, `2m`, `1h`, or a bare number of
+/// seconds.
+pub fn parse_seconds(value: &str) -> Result<u64> {
+    let invalid = || Error::InvalidDuration {
+        value: value.to_string(),
+    };
+    let trimmed = value.trim();
+    let (number, scale) = match trimmed.strip_suffix(['s', 'm', 'h']) {
+        Some(number) => match &trimmed[number.len()..] {
+            "m" => (number, 60),
+            "h" => (number, 3600),
+            _ => (number, 1),
+        },
+        None => (trimmed, 1),
+    };
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    number.checked_mul(scale).ok_or_else(invalid)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
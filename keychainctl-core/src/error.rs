@@ -0,0 +1,144 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// No keychain item exists for the service.
+    NotFound { service: String },
+    /// The `security` utility reported a failure.
+    Security { message: String },
+    /// The `secret-tool` utility reported a failure.
+    SecretService { message: String },
+    /// The `age` utility reported a failure.
+    Age { message: String },
+    /// Security.framework returned an error status.
+    Keychain {
+        action: String,
+        code: i32,
+        message: String,
+    },
+    /// Spawning a process or touching a file failed.
+    Io { action: String, source: io::Error },
+    /// A value that must be text was not valid UTF-8.
+    InvalidUtf8 { what: String },
+    /// The account could not be determined from the environment.
+    UnknownAccount,
+    /// `HOME` is not set, so the config directory cannot be located.
+    HomeNotSet,
+    /// The requested keychain file does not exist.
+    KeychainMissing { path: PathBuf },
+    /// Profile names must be non-empty and contain no `/` or whitespace.
+    InvalidProfile { name: String },
+    /// A TOTP seed or `otpauth://` URI could not be used.
+    InvalidTotp { reason: String },
+    /// The operation is not available on this platform or backend.
+    Unsupported { what: String },
+    /// Tags must be non-empty and free of whitespace, `,`, and `=`.
+    InvalidTag { tag: String },
+    /// A date was not in `YYYY-MM-DD` form.
+    InvalidDate { value: String },
+    /// A duration was not a number with a unit such as `14d` or `30s`.
+    InvalidDuration { value: String },
+    /// A Touch ID prompt was cancelled, failed, or could not be shown.
+    AuthenticationFailed { message: String },
+    /// `config.toml` could not be parsed.
+    InvalidConfig { path: PathBuf, message: String },
+    /// A `{{ secret "..." }}` placeholder was malformed.
+    InvalidTemplate { line: usize, message: String },
+    /// The registry database could not be read or written.
+    Database { action: String, message: String },
+    /// A `--filter` regular expression did not parse.
+    InvalidPattern { pattern: String, message: String },
+}
+
+impl Error {
+    pub(crate) fn io(action: impl Into<String>, source: io::Error) -> Self {
+        Self::Io {
+            action: action.into(),
+            source,
+        }
+    }
+
+    /// Whether the keychain refused a write for lack of privileges, as the
+    /// system keychain does for anyone but root.
+    pub fn is_permission_denied(&self) -> bool {
+        match self {
+            // errSecWrPerm
+            Self::Keychain { code, .. } => *code == -61,
+            Self::Security { message } => message.contains("Write permissions error"),
+            _ => false,
+        }
+    }
+
+    /// Whether the keychain is locked and its password could not be asked
+    /// for, as over SSH or after [`crate::backend::forbid_interaction`].
+    pub fn is_locked(&self) -> bool {
+        match self {
+            // errSecInteractionNotAllowed
+            Self::Keychain { code, .. } => *code == -25308,
+            Self::Security { message } => message.contains("User interaction is not allowed"),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound { service } => {
+                write!(formatter, "secret not found for service `{}`", service)
+            }
+            Self::Security { message } => write!(formatter, "security command failed: {}", message),
+            Self::SecretService { message } => {
+                write!(formatter, "secret-tool command failed: {}", message)
+            }
+            Self::Age { message } => write!(formatter, "age command failed: {}", message),
+            Self::Keychain {
+                action, message, ..
+            } => write!(formatter, "{}: {}", action, message),
+            Self::Io { action, .. } => write!(formatter, "{}", action),
+            Self::InvalidUtf8 { what } => write!(formatter, "{} is not valid UTF-8", what),
+            Self::UnknownAccount => write!(formatter, "failed to determine account"),
+            Self::HomeNotSet => write!(formatter, "HOME not set"),
+            Self::KeychainMissing { path } => {
+                write!(formatter, "keychain {} does not exist", path.display())
+            }
+            Self::InvalidProfile { name } => write!(formatter, "invalid profile name `{}`", name),
+            Self::InvalidTotp { reason } => write!(formatter, "invalid TOTP seed: {}", reason),
+            Self::Unsupported { what } => write!(formatter, "{} is not supported here", what),
+            Self::InvalidTag { tag } => write!(formatter, "invalid tag `{}`", tag),
+            Self::InvalidDate { value } => {
+                write!(formatter, "invalid date `{}` (expected YYYY-MM-DD)", value)
+            }
+            Self::InvalidDuration { value } => {
+                write!(formatter, "invalid duration `{}`", value)
+            }
+            Self::AuthenticationFailed { message } => {
+                write!(formatter, "authentication failed: {}", message)
+            }
+            Self::InvalidConfig { path, message } => {
+                write!(formatter, "invalid config {}: {}", path.display(), message)
+            }
+            Self::InvalidTemplate { line, message } => {
+                write!(formatter, "invalid template: line {}: {}", line, message)
+            }
+            Self::Database { action, message } => write!(formatter, "{}: {}", action, message),
+            Self::InvalidPattern { pattern, message } => {
+                write!(formatter, "invalid pattern `{}`: {}", pattern, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
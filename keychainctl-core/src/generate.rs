@@ -0,0 +1,91 @@
+//! Random passwords and diceware passphrases.
+//!
+//! Randomness comes from `/dev/urandom`, and characters and words are
+//! picked by rejection sampling so every choice is equally likely.
+
+use std::fs::File;
+use std::io::Read;
+
+use crate::{Error, Result};
+
+const RANDOM_DEVICE: &str = "/dev/urandom";
+
+/// The EFF large wordlist (CC BY 3.0, Electronic Frontier Foundation):
+/// 7776 words, one per line after the five dice digits.
+const WORDLIST: &str = include_str!("eff_large_wordlist.txt");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Charset {
+    /// `A-Z`, `a-z`, and `0-9`.
+    Alnum,
+    /// Lowercase hexadecimal digits.
+    Hex,
+    /// The standard base64 alphabet, without padding.
+    Base64,
+}
+
+impl Charset {
+    fn alphabet(self) -> &'static [u8] {
+        match self {
+            Self::Alnum => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+            Self::Hex => b"0123456789abcdef",
+            Self::Base64 => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+        }
+    }
+}
+
+/// Returns `length` random characters drawn from `charset`.
+pub fn password(length: usize, charset: Charset) -> Result<String> {
+    let alphabet = charset.alphabet();
+    let mut random = Random::open()?;
+    let mut password = String::with_capacity(length);
+    for _ in 0..length {
+        password.push(char::from(alphabet[random.below(alphabet.len())?]));
+    }
+    Ok(password)
+}
+
+/// Returns `words` random words from the EFF large wordlist joined by
+/// `separator`. Each word adds about 12.9 bits of entropy.
+pub fn passphrase(words: usize, separator: &str) -> Result<String> {
+    let list: Vec<&str> = WORDLIST
+        .lines()
+        .filter_map(|line| line.split_once('\t').map(|(_, word)| word))
+        .collect();
+    let mut random = Random::open()?;
+    let mut chosen = Vec::with_capacity(words);
+    for _ in 0..words {
+        chosen.push(list[random.below(list.len())?]);
+    }
+    Ok(chosen.join(separator))
+}
+
+struct Random(File);
+
+impl Random {
+    fn open() -> Result<Self> {
+        File::open(RANDOM_DEVICE)
+            .map(Self)
+            .map_err(|error| Error::io(format!("failed to open {}", RANDOM_DEVICE), error))
+    }
+
+    fn fill(&mut self, bytes: &mut [u8]) -> Result<()> {
+        self.0
+            .read_exact(bytes)
+            .map_err(|error| Error::io(format!("failed to read {}", RANDOM_DEVICE), error))
+    }
+
+    /// Returns a uniformly distributed value in `0..bound`.
+    fn below(&mut self, bound: usize) -> Result<usize> {
+        let bound = bound as u32;
+        let limit = u32::MAX - u32::MAX % bound;
+        loop {
+            let mut bytes = [0; 4];
+            self.fill(&mut bytes)?;
+            let value = u32::from_le_bytes(bytes);
+            if value < limit {
+                return Ok((value % bound) as usize);
+            }
+        }
+    }
+}
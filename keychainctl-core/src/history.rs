@@ -0,0 +1,112 @@
+//! Earlier values of a secret, kept as numbered keychain items.
+//!
+//! Version 1 of `github_token` is stored under the service
+//! `github_token@1`, version 2 under `github_token@2`, and so on, newest
+//! first. History items live next to the secret in the same keychain and
+//! are not tracked in the registry.
+
+use crate::backend::Backend;
+use crate::{Error, Result};
+
+/// How many earlier values are kept when `config.toml` does not say.
+pub const DEFAULT_DEPTH: usize = 5;
+
+/// Returns the service name holding version `version` of `service`.
+pub fn item(service: &str, version: usize) -> String {
+    format!("{}@{}", service, version)
+}
+
+/// Stores `value` under `service`, first moving any current value into the
+/// history and dropping versions older than `depth`.
+pub fn store(
+    backend: &dyn Backend,
+    account: &str,
+    service: &str,
+    value: &[u8],
+    depth: usize,
+) -> Result<()> {
+    if depth > 0 {
+        match backend.get_bytes(account, service) {
+            Ok(current) if current != value => push(backend, account, service, &current, depth)?,
+            Ok(_) | Err(Error::NotFound { .. }) => {}
+            Err(error) => return Err(error),
+        }
+    }
+    backend.set_bytes(account, service, value)
+}
+
+/// Makes version `version` the current value. The value it replaces becomes
+/// version 1.
+pub fn rollback(
+    backend: &dyn Backend,
+    account: &str,
+    service: &str,
+    version: usize,
+    depth: usize,
+) -> Result<()> {
+    let value = backend.get_bytes(account, &item(service, version))?;
+    store(backend, account, service, &value, depth.max(1))
+}
+
+/// Lists the stored version numbers of `service`, newest first.
+///
+/// Item timestamps are not reported because shifting versions rewrites
+/// them.
+pub fn list(
+    backend: &dyn Backend,
+    account: &str,
+    service: &str,
+    depth: usize,
+) -> Result<Vec<usize>> {
+    let mut versions = Vec::new();
+    for version in 1..=depth {
+        if backend.exists(account, &item(service, version))? {
+            versions.push(version);
+        }
+    }
+    Ok(versions)
+}
+
+/// Deletes every stored version of `service`.
+pub fn clear(backend: &dyn Backend, account: &str, service: &str, depth: usize) -> Result<()> {
+    for version in 1..=depth {
+        backend.delete(account, &item(service, version))?;
+    }
+    Ok(())
+}
+
+/// Moves the stored versions of `from` to `to`.
+pub fn rename(
+    backend: &dyn Backend,
+    account: &str,
+    from: &str,
+    to: &str,
+    depth: usize,
+) -> Result<()> {
+    for version in 1..=depth {
+        let name = item(from, version);
+        if backend.exists(account, &name)? {
+            let target = item(to, version);
+            backend.delete(account, &target)?;
+            backend.rename(account, &name, &target)?;
+        }
+    }
+    Ok(())
+}
+
+fn push(
+    backend: &dyn Backend,
+    account: &str,
+    service: &str,
+    value: &[u8],
+    depth: usize,
+) -> Result<()> {
+    for version in (1..depth).rev() {
+        match backend.get_bytes(account, &item(service, version)) {
+            Ok(older) => backend.set_bytes(account, &item(service, version + 1), &older)?,
+            Err(Error::NotFound { .. }) => {}
+            Err(error) => return Err(error),
+        }
+    }
+    backend.set_bytes(account, &item(service, 1), value)
+}
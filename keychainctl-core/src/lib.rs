@@ -0,0 +1,45 @@
+//! Library side of `keychainctl`: keychain backends, the service registry,
+//! and account resolution.
+//!
+//! ```no_run
+//! use keychainctl_core::{account, backend, registry};
+//!
+//! let account = account::resolve(None)?;
+//! let store = backend::default_backend();
+//! store.set(&account, "github_token", "ghp_example")?;
+//! registry::add(&account, "github_token")?;
+//! assert_eq!(store.get(&account, "github_token")?, "ghp_example");
+//! # Ok::<(), keychainctl_core::Error>(())
+//! ```
+
+pub mod account;
+pub mod audit;
+pub mod backend;
+pub mod biometrics;
+pub mod chunk;
+pub mod config;
+pub mod date;
+mod error;
+pub mod generate;
+pub mod history;
+pub mod manifest;
+pub mod namespace;
+pub mod profile;
+pub mod pwned;
+pub mod registry;
+pub mod secret;
+pub mod sign;
+pub mod strength;
+pub mod template;
+pub mod totp;
+pub mod trash;
+
+pub use error::{Error, Result};
+
+/// Removes any trailing `\n` and `\r` characters.
+pub fn strip_trailing_newlines(mut value: String) -> String {
+    while matches!(value.as_bytes().last(), Some(b'\n' | b'\r')) {
+        value.pop();
+    }
+    value
+}
@@ -0,0 +1,99 @@
+//! `.keychainctl.toml`, a project's list of the secrets it needs.
+//!
+//! Inside the project, service names without a `/` resolve in the
+//! project's namespace, so `db-password` refers to `myapp/db-password`.
+//!
+//! ```toml
+//! project = "myapp"
+//!
+//! [[secrets]]
+//! name = "db-password"
+//! env = "DB_PASSWORD"
+//! description = "Password of the local Postgres user"
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result, config};
+
+pub const FILE_NAME: &str = ".keychainctl.toml";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Manifest {
+    /// Namespace for the project's secrets, defaulting to the name of the
+    /// directory holding the manifest.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// The secrets the project needs, in the order they are declared.
+    pub secrets: Vec<Secret>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Secret {
+    /// Service name.
+    pub name: String,
+    /// Environment variable the project reads the secret from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+    /// What the secret is and where to get it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl Manifest {
+    /// Returns the project namespace for the manifest at `path`.
+    pub fn project_name(&self, path: &Path) -> Option<String> {
+        self.project
+            .clone()
+            .or_else(|| {
+                let dir = path.parent()?.file_name()?;
+                Some(dir.to_string_lossy().into_owned())
+            })
+            .filter(|name| !name.trim().is_empty())
+    }
+}
+
+/// Looks for `.keychainctl.toml` in `dir` and its parents, returning the
+/// first one found with its path.
+pub fn find(dir: &Path) -> Result<Option<(PathBuf, Manifest)>> {
+    for dir in dir.ancestors() {
+        let path = dir.join(FILE_NAME);
+        match fs::read_to_string(&path) {
+            Ok(data) => {
+                let manifest = config::parse(&path, &data)?;
+                return Ok(Some((path, manifest)));
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => {
+                return Err(Error::io(
+                    format!("failed to read {}", path.display()),
+                    error,
+                ));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Reads the manifest at `path`.
+pub fn load(path: &Path) -> Result<Manifest> {
+    let data = fs::read_to_string(path)
+        .map_err(|error| Error::io(format!("failed to read {}", path.display()), error))?;
+    config::parse(path, &data)
+}
+
+/// Writes `manifest` to `path`, replacing any file there.
+pub fn save(path: &Path, manifest: &Manifest) -> Result<()> {
+    let data = toml::to_string(manifest).map_err(|error| Error::InvalidConfig {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    })?;
+    fs::write(path, data)
+        .map_err(|error| Error::io(format!("failed to write {}", path.display()), error))
+}
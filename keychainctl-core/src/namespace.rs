@@ -0,0 +1,116 @@
+//! Hierarchical service names such as `project/env/name`, split on `/`.
+
+use std::collections::BTreeMap;
+
+use regex_lite::Regex;
+
+use crate::{Error, Result};
+
+pub const SEPARATOR: char = '/';
+
+/// Services grouped by path segment.
+#[derive(Debug, Default)]
+pub struct Tree {
+    /// Full service name when a secret is stored at exactly this path.
+    pub service: Option<String>,
+    pub children: BTreeMap<String, Tree>,
+}
+
+impl Tree {
+    pub fn build<I, S>(services: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut root = Tree::default();
+        for service in services {
+            let service = service.as_ref();
+            let mut node = &mut root;
+            for segment in service.split(SEPARATOR) {
+                node = node.children.entry(segment.to_string()).or_default();
+            }
+            node.service = Some(service.to_string());
+        }
+        root
+    }
+}
+
+/// Selects services by name. Every part that is set must match; an empty
+/// filter matches everything.
+#[derive(Debug, Default)]
+pub struct Filter {
+    /// Shell-style pattern, as understood by [`matches`].
+    glob: Option<String>,
+    /// Regular expression found anywhere in the name unless anchored.
+    regex: Option<Regex>,
+    /// Text the name must contain.
+    contains: Option<String>,
+}
+
+impl Filter {
+    pub fn new(glob: Option<&str>, regex: Option<&str>, contains: Option<&str>) -> Result<Self> {
+        let regex = regex
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|error| Error::InvalidPattern {
+                    pattern: pattern.to_string(),
+                    message: error.to_string(),
+                })
+            })
+            .transpose()?;
+        Ok(Self {
+            glob: glob.map(str::to_string),
+            regex,
+            contains: contains.map(str::to_string),
+        })
+    }
+
+    pub fn matches(&self, service: &str) -> bool {
+        self.glob
+            .as_deref()
+            .is_none_or(|pattern| matches(pattern, service))
+            && self
+                .regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(service))
+            && self
+                .contains
+                .as_deref()
+                .is_none_or(|text| service.contains(text))
+    }
+}
+
+/// Returns whether `pattern` contains `*` or `?`.
+pub fn is_pattern(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// Matches a service name against a shell-style pattern, where `*` matches
+/// any run of characters, `/` included, and `?` matches one character.
+pub fn matches(pattern: &str, service: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let service: Vec<char> = service.chars().collect();
+    let (mut p, mut s) = (0, 0);
+    // Where the last `*` was seen, and how much of `service` it has taken.
+    let mut star = None;
+    while s < service.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, s));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == service[s] => {
+                p += 1;
+                s += 1;
+            }
+            _ => match star {
+                Some((star_p, star_s)) => {
+                    star = Some((star_p, star_s + 1));
+                    p = star_p + 1;
+                    s = star_s + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
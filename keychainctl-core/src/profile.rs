@@ -0,0 +1,124 @@
+//! Named sets of secrets such as `dev`, `staging`, and `prod`.
+//!
+//! A profile is a service namespace: with `staging` active, the service
+//! `db-password` refers to the keychain item `staging/db-password`.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::namespace::SEPARATOR;
+use crate::{Error, Result, config};
+
+/// Prefixes `service` with the profile namespace, if any.
+pub fn scope(profile: Option<&str>, service: &str) -> String {
+    match profile {
+        Some(profile) => format!("{}{}{}", profile, SEPARATOR, service),
+        None => service.to_string(),
+    }
+}
+
+/// Returns the service prefix for `profile`, e.g. `staging/`.
+pub fn prefix(profile: Option<&str>) -> String {
+    scope(profile, "")
+}
+
+pub fn validate(name: &str) -> Result<()> {
+    if name.trim().is_empty() || name.contains(SEPARATOR) || name.contains(char::is_whitespace) {
+        return Err(Error::InvalidProfile {
+            name: name.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Returns the active profile recorded by [`set_active`].
+pub fn active() -> Result<Option<String>> {
+    match fs::read_to_string(active_path()?) {
+        Ok(data) => Ok(Some(data.trim().to_string()).filter(|name| !name.is_empty())),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(Error::io("failed to read active profile", error)),
+    }
+}
+
+/// Records `profile` as active (adding it to the known profiles), or clears
+/// the active profile when `None`.
+pub fn set_active(profile: Option<&str>) -> Result<()> {
+    let path = active_path()?;
+    match profile {
+        Some(name) => {
+            add(name)?;
+            fs::write(&path, format!("{}\n", name))
+                .map_err(|error| Error::io("failed to write active profile", error))
+        }
+        None => match fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(Error::io("failed to clear active profile", error)),
+        },
+    }
+}
+
+/// Returns the known profiles in sorted order.
+pub fn list() -> Result<Vec<String>> {
+    Ok(load()?.into_iter().collect())
+}
+
+pub fn add(name: &str) -> Result<()> {
+    validate(name)?;
+    let mut profiles = load()?;
+    if profiles.insert(name.to_string()) {
+        save(&profiles)?;
+    }
+    Ok(())
+}
+
+/// Forgets `name`, clearing it if it is active. Secrets stored under the
+/// profile are left alone.
+pub fn remove(name: &str) -> Result<()> {
+    let mut profiles = load()?;
+    if profiles.remove(name) {
+        save(&profiles)?;
+    }
+    if active()?.as_deref() == Some(name) {
+        set_active(None)?;
+    }
+    Ok(())
+}
+
+fn active_path() -> Result<PathBuf> {
+    Ok(config::dir()?.join("profile"))
+}
+
+fn profiles_path() -> Result<PathBuf> {
+    Ok(config::dir()?.join("profiles.txt"))
+}
+
+fn load() -> Result<BTreeSet<String>> {
+    let mut profiles = BTreeSet::new();
+    if let Ok(data) = fs::read_to_string(profiles_path()?) {
+        for line in data.lines() {
+            let trimmed = line.trim();
+            if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                profiles.insert(trimmed.to_string());
+            }
+        }
+    }
+    Ok(profiles)
+}
+
+fn save(profiles: &BTreeSet<String>) -> Result<()> {
+    let path = profiles_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| Error::io("failed to create config directory", error))?;
+    }
+
+    let mut data = String::new();
+    for profile in profiles {
+        data.push_str(profile);
+        data.push('\n');
+    }
+    fs::write(&path, data).map_err(|error| Error::io("failed to write profiles file", error))
+}
@@ -0,0 +1,43 @@
+//! Breach lookups against the Pwned Passwords range API.
+//!
+//! Only the first 5 hex digits of a value's SHA-1 hash leave the machine;
+//! the API answers with the suffixes of every breached hash sharing that
+//! prefix, and the match is made locally.
+
+use sha1::{Digest, Sha1};
+
+/// The range endpoint; the hash prefix is appended to it.
+pub const RANGE_URL: &str = "https://api.pwnedpasswords.com/range/";
+
+/// A value's SHA-1 hash in uppercase hex, split into the 5-digit prefix
+/// sent to the API and the suffix matched against its answer.
+pub struct Hash {
+    pub prefix: String,
+    pub suffix: String,
+}
+
+impl Hash {
+    pub fn new(value: &[u8]) -> Self {
+        let hex: String = Sha1::digest(value)
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+        let (prefix, suffix) = hex.split_at(5);
+        Self {
+            prefix: prefix.to_string(),
+            suffix: suffix.to_string(),
+        }
+    }
+
+    /// Returns how many times the hash appears in breaches, given the
+    /// API's `SUFFIX:COUNT` lines for its prefix. Padding lines have a
+    /// count of 0.
+    pub fn count(&self, response: &str) -> u64 {
+        response
+            .lines()
+            .filter_map(|line| line.trim().split_once(':'))
+            .find(|(suffix, _)| suffix.eq_ignore_ascii_case(&self.suffix))
+            .and_then(|(_, count)| count.trim().parse().ok())
+            .unwrap_or(0)
+    }
+}
@@ -0,0 +1,663 @@
+//! Index of the services stored per account, with their metadata.
+//!
+//! The keychain cannot be enumerated cheaply, so every `set` records the
+//! service here and every `delete` removes it again. Entries are rows of the
+//! SQLite database `registry.db` in the config directory, keyed by account
+//! and service, with tags, notes, an expiry date, the type of secret, its
+//! environment variable, and when the secret was first tracked, last
+//! changed, and last read.
+//!
+//! With `registry = "keychain"` in `config.toml`, the registry is kept in the
+//! keychain item [`ITEM_SERVICE`] instead, so the list of services is
+//! protected like the secrets themselves. The item holds one
+//! `account<TAB>service` line per entry, followed by `<TAB>key=value` fields
+//! such as `expires=2025-01-01`, `tags=ci,aws`, `type=token`, or `notes=...`
+//! (with tabs, newlines, and backslashes escaped). Reads are not recorded
+//! there, so a `get` never rewrites the item.
+//!
+//! Earlier versions kept the same lines in `registry.txt`, which is moved
+//! into the database the first time it is opened.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rusqlite::{Connection, TransactionBehavior, params};
+
+use crate::backend::Backend;
+use crate::date::{self, Date};
+use crate::{Error, Result, config};
+
+/// The account of the keychain item holding the registry.
+pub const ITEM_ACCOUNT: &str = "keychainctl";
+/// The service of the keychain item holding the registry.
+pub const ITEM_SERVICE: &str = "keychainctl/registry";
+
+/// How long to wait for another keychainctl process to finish writing.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS services (
+    account TEXT NOT NULL,
+    service TEXT NOT NULL,
+    expires TEXT,
+    tags TEXT NOT NULL DEFAULT '',
+    notes TEXT,
+    type TEXT,
+    env_var TEXT,
+    created TEXT,
+    updated TEXT,
+    accessed TEXT,
+    PRIMARY KEY (account, service)
+) WITHOUT ROWID";
+
+thread_local! {
+    /// The keychain holding the registry, when it is not kept in a file.
+    static KEYCHAIN: RefCell<Option<Box<dyn Backend>>> = const { RefCell::new(None) };
+    /// Whether this thread holds `registry.lock`.
+    static LOCKED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Entries keyed by account, then by service.
+pub type Registry = BTreeMap<String, BTreeMap<String, Entry>>;
+
+/// Metadata kept alongside a tracked service.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Entry {
+    /// The day the secret is due for rotation.
+    pub expires: Option<Date>,
+    /// Free-form labels such as `ci` or `aws`.
+    pub tags: BTreeSet<String>,
+    /// Free-form text, such as where the secret came from.
+    pub notes: Option<String>,
+    /// What the value is, as given to `set --type`.
+    pub secret_type: Option<SecretType>,
+    /// The environment variable `run`, `env`, and `export` put the secret
+    /// in, instead of one named after the service.
+    pub env_var: Option<String>,
+    /// When the service was first tracked, in RFC 3339 form.
+    pub created: Option<String>,
+    /// When the secret or its metadata last changed.
+    pub updated: Option<String>,
+    /// When the secret was last read, if that was recorded.
+    pub accessed: Option<String>,
+}
+
+/// The kinds of secret `set --type` records.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecretType {
+    Password,
+    Token,
+    ApiKey,
+    SshKey,
+    Certificate,
+    Json,
+}
+
+impl SecretType {
+    pub const ALL: [Self; 6] = [
+        Self::Password,
+        Self::Token,
+        Self::ApiKey,
+        Self::SshKey,
+        Self::Certificate,
+        Self::Json,
+    ];
+
+    /// The name used on the command line and in the registry.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Password => "password",
+            Self::Token => "token",
+            Self::ApiKey => "api-key",
+            Self::SshKey => "ssh-key",
+            Self::Certificate => "certificate",
+            Self::Json => "json",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// Tags must be non-empty and contain no whitespace, `,`, or `=`, so they
+/// fit in a registry field.
+pub fn validate_tag(tag: &str) -> Result<()> {
+    if tag.is_empty()
+        || tag.contains(|character: char| character.is_whitespace() || ",=".contains(character))
+    {
+        return Err(Error::InvalidTag {
+            tag: tag.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Tracks `service`, keeping any metadata it already has.
+pub fn add(account: &str, service: &str) -> Result<()> {
+    update(account, service, |_| {})
+}
+
+/// Tracks `service` and applies `change` to its metadata, stamping it as
+/// updated now.
+pub fn update(account: &str, service: &str, change: impl FnOnce(&mut Entry)) -> Result<()> {
+    modify(|registry| {
+        let now = date::timestamp();
+        let entry = registry
+            .entry(account.to_string())
+            .or_default()
+            .entry(service.to_string())
+            .or_default();
+        change(entry);
+        entry.created.get_or_insert_with(|| now.clone());
+        entry.updated = Some(now);
+    })
+}
+
+pub fn remove(account: &str, service: &str) -> Result<()> {
+    modify(|registry| {
+        if let Some(services) = registry.get_mut(account) {
+            services.remove(service);
+            if services.is_empty() {
+                registry.remove(account);
+            }
+        }
+    })
+}
+
+/// Moves the entry for `from`, metadata included, to `to`.
+pub fn rename(account: &str, from: &str, to: &str) -> Result<()> {
+    modify(|registry| {
+        let services = registry.entry(account.to_string()).or_default();
+        let mut entry = services.remove(from).unwrap_or_default();
+        entry.updated = Some(date::timestamp());
+        services.insert(to.to_string(), entry);
+    })
+}
+
+/// Records that `service` was just read. Untracked services are left
+/// alone, as is the registry when it is kept in the keychain.
+pub fn touch(account: &str, service: &str) -> Result<()> {
+    if in_keychain() {
+        return Ok(());
+    }
+    let connection = open()?;
+    connection
+        .execute(
+            "UPDATE services SET accessed = ?1 WHERE account = ?2 AND service = ?3",
+            params![date::timestamp(), account, service],
+        )
+        .map_err(failed("failed to update the registry"))?;
+    Ok(())
+}
+
+/// Returns the metadata for `service`, if it is tracked.
+pub fn entry(account: &str, service: &str) -> Result<Option<Entry>> {
+    Ok(load()?
+        .get(account)
+        .and_then(|services| services.get(service))
+        .cloned())
+}
+
+/// Returns the accounts with tracked services in sorted order.
+pub fn accounts() -> Result<Vec<String>> {
+    Ok(load()?
+        .into_iter()
+        .filter(|(_, services)| !services.is_empty())
+        .map(|(account, _)| account)
+        .collect())
+}
+
+/// Returns the tracked services for `account` in sorted order.
+pub fn list(account: &str) -> Result<Vec<String>> {
+    list_prefix(account, "")
+}
+
+/// Returns the tracked services for `account` whose names start with
+/// `prefix`, such as `project/` for everything under that namespace.
+pub fn list_prefix(account: &str, prefix: &str) -> Result<Vec<String>> {
+    Ok(entries_prefix(account, prefix)?
+        .into_iter()
+        .map(|(service, _)| service)
+        .collect())
+}
+
+/// Like [`list_prefix`], but includes each service's metadata.
+pub fn entries_prefix(account: &str, prefix: &str) -> Result<Vec<(String, Entry)>> {
+    let registry = load()?;
+    let entries = registry
+        .get(account)
+        .map(|services| {
+            services
+                .range(prefix.to_string()..)
+                .take_while(|(service, _)| service.starts_with(prefix))
+                .map(|(service, entry)| (service.clone(), entry.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(entries)
+}
+
+pub fn path() -> Result<PathBuf> {
+    Ok(config::dir()?.join("registry.db"))
+}
+
+/// The file locked while the registry is changed.
+fn lock_path() -> Result<PathBuf> {
+    Ok(config::dir()?.join("registry.lock"))
+}
+
+/// The tab-separated file earlier versions kept the registry in.
+fn legacy_path() -> Result<PathBuf> {
+    Ok(config::dir()?.join("registry.txt"))
+}
+
+/// Keeps the registry in `backend` instead of `registry.db`, first moving
+/// in the entries of `registry.db` (or `registry.txt`) if it exists.
+pub fn keep_in_keychain(backend: Box<dyn Backend>) -> Result<()> {
+    let path = path()?;
+    if !path.exists() && !legacy_path()?.exists() {
+        KEYCHAIN.set(Some(backend));
+        return Ok(());
+    }
+    let _lock = lock()?;
+    let in_file = if path.exists() || legacy_path()?.exists() {
+        Some(read_database(&open()?)?)
+    } else {
+        None
+    };
+    KEYCHAIN.set(Some(backend));
+    if let Some(in_file) = in_file {
+        modify(|registry| {
+            for (account, services) in in_file {
+                registry.entry(account).or_default().extend(services);
+            }
+        })?;
+        remove_file(&path)?;
+    }
+    Ok(())
+}
+
+/// Moves the registry out of the keychain and back into `registry.db`.
+/// Does nothing when it is already kept in the file.
+pub fn move_to_file() -> Result<()> {
+    let Some(backend) = KEYCHAIN.take() else {
+        return Ok(());
+    };
+    let _lock = lock()?;
+    let registry = parse(&read_item(&*backend)?);
+    save(&registry)?;
+    backend.delete(ITEM_ACCOUNT, ITEM_SERVICE)
+}
+
+/// Returns whether the registry is kept in the keychain.
+pub fn in_keychain() -> bool {
+    KEYCHAIN.with_borrow(Option::is_some)
+}
+
+pub fn load() -> Result<Registry> {
+    KEYCHAIN.with_borrow(|keychain| match keychain {
+        Some(backend) => Ok(parse(&read_item(&**backend)?)),
+        None => read_database(&open()?),
+    })
+}
+
+pub fn save(map: &Registry) -> Result<()> {
+    let _lock = lock()?;
+    KEYCHAIN.with_borrow(|keychain| match keychain {
+        Some(backend) => backend.set(ITEM_ACCOUNT, ITEM_SERVICE, &format(map)),
+        None => {
+            let mut connection = open()?;
+            let transaction = connection
+                .transaction()
+                .map_err(failed("failed to update the registry"))?;
+            write_database(&transaction, map)?;
+            transaction
+                .commit()
+                .map_err(failed("failed to update the registry"))
+        }
+    })
+}
+
+/// Loads the registry, applies `change`, and saves it, holding
+/// `registry.lock` throughout so concurrent changes are not lost.
+fn modify(change: impl FnOnce(&mut Registry)) -> Result<()> {
+    let _lock = lock()?;
+    if in_keychain() {
+        let mut registry = load()?;
+        change(&mut registry);
+        return save(&registry);
+    }
+    let mut connection = open()?;
+    let transaction = connection
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(failed("failed to lock the registry"))?;
+    let mut registry = read_database(&transaction)?;
+    change(&mut registry);
+    write_database(&transaction, &registry)?;
+    transaction
+        .commit()
+        .map_err(failed("failed to update the registry"))
+}
+
+/// Opens `registry.db`, creating it readable only by its owner, and moves
+/// in the entries of `registry.txt` if that file is still around.
+fn open() -> Result<Connection> {
+    let path = path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| Error::io("failed to create registry directory", error))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(&path)
+        .map_err(|error| Error::io(format!("failed to create {}", path.display()), error))?;
+    let action = format!("failed to open {}", path.display());
+    let mut connection = Connection::open(&path).map_err(failed(&action))?;
+    connection
+        .busy_timeout(BUSY_TIMEOUT)
+        .and_then(|()| connection.execute_batch(SCHEMA))
+        .and_then(|()| add_columns(&connection))
+        .map_err(failed(&action))?;
+
+    let legacy = legacy_path()?;
+    if !legacy.exists() {
+        return Ok(connection);
+    }
+    let _lock = lock()?;
+    // Another process may have moved the file in while we waited.
+    let data = match fs::read_to_string(&legacy) {
+        Ok(data) => data,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(connection),
+        Err(error) => {
+            return Err(Error::io(
+                format!("failed to read {}", legacy.display()),
+                error,
+            ));
+        }
+    };
+    let transaction = connection
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(failed("failed to lock the registry"))?;
+    let mut registry = read_database(&transaction)?;
+    for (account, services) in parse(&data) {
+        registry.entry(account).or_default().extend(services);
+    }
+    write_database(&transaction, &registry)?;
+    transaction
+        .commit()
+        .map_err(failed("failed to update the registry"))?;
+    remove_file(&legacy)?;
+    Ok(connection)
+}
+
+/// Holds `registry.lock` until dropped.
+pub struct Lock {
+    /// `None` for a guard nested inside one this thread already holds.
+    file: Option<File>,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if self.file.is_some() {
+            LOCKED.set(false);
+        }
+    }
+}
+
+/// Takes an exclusive advisory lock on `registry.lock`, waiting for other
+/// processes to release it. Callers hold it to read and rewrite a secret
+/// without another keychainctl changing it in between; the registry's own
+/// changes take it again without waiting.
+pub fn lock() -> Result<Lock> {
+    if LOCKED.get() {
+        return Ok(Lock { file: None });
+    }
+    let path = lock_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| Error::io("failed to create registry directory", error))?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .mode(0o600)
+        .open(&path)
+        .map_err(|error| Error::io(format!("failed to open {}", path.display()), error))?;
+    file.lock()
+        .map_err(|error| Error::io(format!("failed to lock {}", path.display()), error))?;
+    LOCKED.set(true);
+    Ok(Lock { file: Some(file) })
+}
+
+/// Removes a file that another process may already have removed.
+fn remove_file(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(Error::io(
+            format!("failed to remove {}", path.display()),
+            error,
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Adds the columns a database created by an earlier version lacks.
+fn add_columns(connection: &Connection) -> rusqlite::Result<()> {
+    for column in ["type", "env_var"] {
+        let present: bool = connection.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('services') WHERE name = ?1",
+            [column],
+            |row| row.get(0),
+        )?;
+        if !present {
+            connection
+                .execute_batch(&format!("ALTER TABLE services ADD COLUMN {} TEXT", column))?;
+        }
+    }
+    Ok(())
+}
+
+fn read_database(connection: &Connection) -> Result<Registry> {
+    let read = || -> rusqlite::Result<Registry> {
+        let mut statement = connection.prepare(
+            "SELECT account, service, expires, tags, notes, created, updated, accessed, type,
+                    env_var
+             FROM services",
+        )?;
+        let rows = statement.query_map([], |row| {
+            let expires: Option<String> = row.get(2)?;
+            let tags: String = row.get(3)?;
+            let secret_type: Option<String> = row.get(8)?;
+            let entry = Entry {
+                expires: expires.and_then(|expires| Date::parse(&expires).ok()),
+                tags: parse_tags(&tags),
+                notes: row.get(4)?,
+                created: row.get(5)?,
+                updated: row.get(6)?,
+                accessed: row.get(7)?,
+                secret_type: secret_type.as_deref().and_then(SecretType::parse),
+                env_var: row.get(9)?,
+            };
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, entry))
+        })?;
+        let mut registry = Registry::new();
+        for row in rows {
+            let (account, service, entry) = row?;
+            registry.entry(account).or_default().insert(service, entry);
+        }
+        Ok(registry)
+    };
+    read().map_err(failed("failed to read the registry"))
+}
+
+/// Replaces every row with the entries of `map`.
+fn write_database(connection: &Connection, map: &Registry) -> Result<()> {
+    let write = || -> rusqlite::Result<()> {
+        connection.execute("DELETE FROM services", [])?;
+        let mut statement = connection.prepare(
+            "INSERT INTO services
+             (account, service, expires, tags, notes, created, updated, accessed, type, env_var)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        )?;
+        for (account, services) in map {
+            for (service, entry) in services {
+                statement.execute(params![
+                    account,
+                    service,
+                    entry.expires.map(|expires| expires.to_string()),
+                    format_tags(&entry.tags),
+                    entry.notes,
+                    entry.created,
+                    entry.updated,
+                    entry.accessed,
+                    entry.secret_type.map(SecretType::name),
+                    entry.env_var,
+                ])?;
+            }
+        }
+        Ok(())
+    };
+    write().map_err(failed("failed to update the registry"))
+}
+
+fn failed(action: &str) -> impl FnOnce(rusqlite::Error) -> Error {
+    let action = action.to_string();
+    move |error| Error::Database {
+        action,
+        message: error.to_string(),
+    }
+}
+
+/// Reads the registry item, treating a missing one as empty.
+fn read_item(backend: &dyn Backend) -> Result<String> {
+    match backend.get(ITEM_ACCOUNT, ITEM_SERVICE) {
+        Err(Error::NotFound { .. }) => Ok(String::new()),
+        result => result,
+    }
+}
+
+fn parse_tags(value: &str) -> BTreeSet<String> {
+    value
+        .split(',')
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn format_tags(tags: &BTreeSet<String>) -> String {
+    tags.iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse(data: &str) -> Registry {
+    let mut map = Registry::new();
+    for line in data.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let mut fields = trimmed.split('\t');
+        let (Some(account), Some(service)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let mut entry = Entry::default();
+        for field in fields {
+            // Skip unknown or malformed fields so older builds can read
+            // registries written by newer ones.
+            match field.split_once('=') {
+                Some(("expires", value)) => entry.expires = Date::parse(value).ok(),
+                Some(("tags", value)) => entry.tags = parse_tags(value),
+                Some(("notes", value)) => entry.notes = Some(unescape(value)),
+                Some(("type", value)) => entry.secret_type = SecretType::parse(value),
+                Some(("env", value)) => entry.env_var = Some(value.to_string()),
+                Some(("created", value)) => entry.created = Some(value.to_string()),
+                Some(("updated", value)) => entry.updated = Some(value.to_string()),
+                Some(("accessed", value)) => entry.accessed = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        map.entry(account.to_string())
+            .or_default()
+            .insert(service.to_string(), entry);
+    }
+    map
+}
+
+fn format(map: &Registry) -> String {
+    let mut data = String::new();
+    for (account, services) in map {
+        for (service, entry) in services {
+            data.push_str(account);
+            data.push('\t');
+            data.push_str(service);
+            if let Some(expires) = &entry.expires {
+                data.push_str(&format!("\texpires={}", expires));
+            }
+            if !entry.tags.is_empty() {
+                data.push_str(&format!("\ttags={}", format_tags(&entry.tags)));
+            }
+            if let Some(notes) = &entry.notes {
+                data.push_str(&format!("\tnotes={}", escape(notes)));
+            }
+            if let Some(secret_type) = entry.secret_type {
+                data.push_str(&format!("\ttype={}", secret_type.name()));
+            }
+            if let Some(env_var) = &entry.env_var {
+                data.push_str(&format!("\tenv={}", env_var));
+            }
+            for (key, value) in [
+                ("created", &entry.created),
+                ("updated", &entry.updated),
+                ("accessed", &entry.accessed),
+            ] {
+                if let Some(value) = value {
+                    data.push_str(&format!("\t{}={}", key, value));
+                }
+            }
+            data.push('\n');
+        }
+    }
+    data
+}
+
+pub(crate) fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            other => escaped.push(other),
+        }
+    }
+    escaped
+}
+
+pub(crate) fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut characters = value.chars();
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            unescaped.push(character);
+            continue;
+        }
+        match characters.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
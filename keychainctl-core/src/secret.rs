@@ -0,0 +1,14 @@
+//! Secret values that are wiped from memory when dropped.
+//!
+//! The buffer is overwritten with zeros before it is freed, so plaintext
+//! does not linger in the heap of a long-running process such as
+//! `keychainctl agent`. Copies made outside keychainctl, by the `security`
+//! utility or in pipes to other programs, are out of its reach.
+
+pub use zeroize::Zeroizing;
+
+/// A UTF-8 secret value.
+pub type SecretString = Zeroizing<String>;
+
+/// A secret value of arbitrary bytes.
+pub type SecretBytes = Zeroizing<Vec<u8>>;
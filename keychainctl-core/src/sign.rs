@@ -0,0 +1,36 @@
+//! HMAC signatures, as webhook senders such as GitHub and Stripe compute
+//! them over the request body.
+
+use hmac::{EagerHash, Hmac, KeyInit, Mac};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    HmacSha1,
+    HmacSha256,
+    HmacSha512,
+}
+
+impl Algorithm {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::HmacSha1 => "hmac-sha1",
+            Self::HmacSha256 => "hmac-sha256",
+            Self::HmacSha512 => "hmac-sha512",
+        }
+    }
+}
+
+/// Signs `message` with `key`, which may be any length.
+pub fn sign(algorithm: Algorithm, key: &[u8], message: &[u8]) -> Vec<u8> {
+    match algorithm {
+        Algorithm::HmacSha1 => hmac::<sha1::Sha1>(key, message),
+        Algorithm::HmacSha256 => hmac::<sha2::Sha256>(key, message),
+        Algorithm::HmacSha512 => hmac::<sha2::Sha512>(key, message),
+    }
+}
+
+pub(crate) fn hmac<D: EagerHash>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<D>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
@@ -0,0 +1,57 @@
+//! Password strength estimates, using zxcvbn's pattern matching.
+//!
+//! Scores run from 0 (guessed almost instantly) to 4 (very hard to guess).
+//! Values that an attacker could find in a dictionary, a keyboard walk, or
+//! the service name itself score low however long they are.
+
+/// The lowest score that is not reported as weak.
+pub const ACCEPTABLE: u8 = 3;
+
+/// The highest score there is.
+pub const MAX: u8 = 4;
+
+/// How guessable a value is.
+#[derive(Clone, Debug)]
+pub struct Strength {
+    /// From 0 to [`MAX`].
+    pub score: u8,
+    /// How long an offline attack against a slow hash would take, such as
+    /// `3 hours`.
+    pub crack_time: String,
+    /// What makes the value weak, when something specific does.
+    pub warning: Option<String>,
+    /// How to pick a better value.
+    pub suggestions: Vec<String>,
+}
+
+impl Strength {
+    pub fn is_weak(&self) -> bool {
+        self.score < ACCEPTABLE
+    }
+}
+
+/// Estimates the strength of `value`. Words in `context`, such as the
+/// service and account names, count as easy to guess.
+pub fn estimate(value: &str, context: &[&str]) -> Strength {
+    let entropy = zxcvbn::zxcvbn(value, context);
+    let feedback = entropy.feedback();
+    Strength {
+        score: entropy.score().into(),
+        crack_time: entropy
+            .crack_times()
+            .offline_slow_hashing_1e4_per_second()
+            .to_string(),
+        warning: feedback
+            .and_then(|feedback| feedback.warning())
+            .map(|warning| warning.to_string()),
+        suggestions: feedback
+            .map(|feedback| {
+                feedback
+                    .suggestions()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
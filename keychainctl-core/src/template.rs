@@ -0,0 +1,114 @@
+//! Text templates with `{{ secret "service" }}` placeholders.
+//!
+//! Only the `secret` placeholder is interpreted. Any other `{{ ... }}` is
+//! copied through unchanged, so templates for tools with their own
+//! `{{ }}` syntax keep working.
+
+use crate::{Error, Result};
+
+const OPEN: &str = "{{";
+const CLOSE: &str = "}}";
+const KEYWORD: &str = "secret";
+
+/// A parsed template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Secret(String),
+}
+
+impl Template {
+    /// Parses `source`, failing on a malformed `secret` placeholder.
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut rest = source;
+        while let Some(start) = rest.find(OPEN) {
+            text.push_str(&rest[..start]);
+            let after = &rest[start + OPEN.len()..];
+            let Some(body) = after.trim_start().strip_prefix(KEYWORD) else {
+                text.push_str(OPEN);
+                rest = after;
+                continue;
+            };
+            if !body.starts_with(char::is_whitespace) {
+                text.push_str(OPEN);
+                rest = after;
+                continue;
+            }
+
+            let line = source[..source.len() - rest.len() + start]
+                .matches('\n')
+                .count()
+                + 1;
+            let invalid = |message: &str| Error::InvalidTemplate {
+                line,
+                message: message.to_string(),
+            };
+            let (service, body) = parse_string(body.trim_start())
+                .ok_or_else(|| invalid("expected a quoted service name after `secret`"))?;
+            if service.is_empty() {
+                return Err(invalid("service name is empty"));
+            }
+            rest = body
+                .trim_start()
+                .strip_prefix(CLOSE)
+                .ok_or_else(|| invalid("expected `}}` after the service name"))?;
+
+            if !text.is_empty() {
+                pieces.push(Piece::Text(std::mem::take(&mut text)));
+            }
+            pieces.push(Piece::Secret(service));
+        }
+        text.push_str(rest);
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+        Ok(Self { pieces })
+    }
+
+    /// Returns the services the template refers to, in order of appearance
+    /// and possibly repeated.
+    pub fn services(&self) -> impl Iterator<Item = &str> {
+        self.pieces.iter().filter_map(|piece| match piece {
+            Piece::Secret(service) => Some(service.as_str()),
+            Piece::Text(_) => None,
+        })
+    }
+
+    /// Renders the template, calling `lookup` for each placeholder.
+    pub fn render<E>(
+        &self,
+        mut lookup: impl FnMut(&str) -> std::result::Result<String, E>,
+    ) -> std::result::Result<String, E> {
+        let mut output = String::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => output.push_str(text),
+                Piece::Secret(service) => output.push_str(&lookup(service)?),
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Reads a double-quoted string with `\"` and `\\` escapes, returning it
+/// and the text after the closing quote.
+fn parse_string(input: &str) -> Option<(String, &str)> {
+    let mut characters = input.strip_prefix('"')?.char_indices();
+    let mut value = String::new();
+    while let Some((index, character)) = characters.next() {
+        match character {
+            '"' => return Some((value, &input[index + 2..])),
+            '\\' => value.push(characters.next()?.1),
+            '\n' => return None,
+            other => value.push(other),
+        }
+    }
+    None
+}
@@ -0,0 +1,195 @@
+//! RFC 6238 time-based one-time passwords.
+//!
+//! Seeds are stored in the keychain as `otpauth://totp/...` URIs so the
+//! digit count, period, and algorithm travel with the secret.
+
+use crate::sign::hmac;
+use crate::{Error, Result};
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl Algorithm {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Sha1 => "SHA1",
+            Self::Sha256 => "SHA256",
+            Self::Sha512 => "SHA512",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Totp {
+    pub secret: Vec<u8>,
+    pub algorithm: Algorithm,
+    pub digits: u32,
+    pub period: u64,
+}
+
+impl Totp {
+    /// Uses the common defaults: SHA-1, six digits, 30 second period.
+    pub fn new(secret: Vec<u8>) -> Self {
+        Self {
+            secret,
+            algorithm: Algorithm::Sha1,
+            digits: 6,
+            period: 30,
+        }
+    }
+
+    /// Parses a bare base32 seed or an `otpauth://totp/...` URI.
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let Some(rest) = value.strip_prefix("otpauth://totp/") else {
+            return Ok(Self::new(decode_base32(value)?));
+        };
+
+        let query = rest.split_once('?').map(|(_, query)| query).unwrap_or("");
+        let mut secret = None;
+        let mut totp = Self::new(Vec::new());
+        for pair in query.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            match key.to_ascii_lowercase().as_str() {
+                "secret" => secret = Some(decode_base32(value)?),
+                "algorithm" => {
+                    totp.algorithm = match value.to_ascii_uppercase().as_str() {
+                        "SHA1" => Algorithm::Sha1,
+                        "SHA256" => Algorithm::Sha256,
+                        "SHA512" => Algorithm::Sha512,
+                        _ => return Err(invalid(format!("unknown algorithm `{}`", value))),
+                    }
+                }
+                "digits" => {
+                    totp.digits = value
+                        .parse()
+                        .map_err(|_| invalid(format!("invalid digits `{}`", value)))?
+                }
+                "period" => {
+                    totp.period = value
+                        .parse()
+                        .map_err(|_| invalid(format!("invalid period `{}`", value)))?
+                }
+                _ => {}
+            }
+        }
+        totp.secret = secret.ok_or_else(|| invalid("URI has no secret".to_string()))?;
+        totp.validate()?;
+        Ok(totp)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.secret.is_empty() {
+            return Err(invalid("secret is empty".to_string()));
+        }
+        if !(6..=10).contains(&self.digits) {
+            return Err(invalid(format!("digits must be 6-10, got {}", self.digits)));
+        }
+        if self.period == 0 {
+            return Err(invalid("period must be positive".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Renders the seed as an `otpauth://` URI labelled with `label`.
+    pub fn to_uri(&self, label: &str) -> String {
+        let label: String = label
+            .bytes()
+            .map(|byte| {
+                if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+                    (byte as char).to_string()
+                } else {
+                    format!("%{:02X}", byte)
+                }
+            })
+            .collect();
+        format!(
+            "otpauth://totp/{}?secret={}&algorithm={}&digits={}&period={}",
+            label,
+            encode_base32(&self.secret),
+            self.algorithm.name(),
+            self.digits,
+            self.period
+        )
+    }
+
+    /// Returns the code valid at `unix_time`, zero-padded to `digits`.
+    pub fn code_at(&self, unix_time: u64) -> String {
+        let counter = (unix_time / self.period).to_be_bytes();
+        let hash = match self.algorithm {
+            Algorithm::Sha1 => hmac::<sha1::Sha1>(&self.secret, &counter),
+            Algorithm::Sha256 => hmac::<sha2::Sha256>(&self.secret, &counter),
+            Algorithm::Sha512 => hmac::<sha2::Sha512>(&self.secret, &counter),
+        };
+
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            hash[offset] & 0x7f,
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ]);
+        let code = u64::from(binary) % 10u64.pow(self.digits);
+        format!("{:0width$}", code, width = self.digits as usize)
+    }
+
+    /// Seconds until the code valid at `unix_time` expires.
+    pub fn remaining(&self, unix_time: u64) -> u64 {
+        self.period - unix_time % self.period
+    }
+}
+
+fn invalid(reason: String) -> Error {
+    Error::InvalidTotp { reason }
+}
+
+/// Decodes RFC 4648 base32, ignoring case, spaces, dashes, and padding.
+pub fn decode_base32(input: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for character in input.chars() {
+        if character.is_whitespace() || character == '-' || character == '=' {
+            continue;
+        }
+        let upper = character.to_ascii_uppercase();
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&symbol| symbol as char == upper)
+            .ok_or_else(|| invalid(format!("`{}` is not a base32 character", character)))?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Ok(bytes)
+}
+
+/// Encodes bytes as unpadded RFC 4648 base32.
+pub fn encode_base32(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
@@ -0,0 +1,72 @@
+//! Deleted secrets, kept until the trash is emptied.
+//!
+//! Deleting `github_token` at 2024-01-02T03:04:05Z moves it to the service
+//! `trash/2024-01-02T03:04:05Z/github_token`, in the same keychain and
+//! account. Trash items are not tracked in the registry.
+
+use std::collections::BTreeSet;
+
+use crate::Result;
+use crate::backend::Backend;
+
+/// The namespace trash items are stored under.
+pub const PREFIX: &str = "trash/";
+
+/// A deleted secret.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Item {
+    /// The service the secret was deleted from.
+    pub service: String,
+    /// When it was deleted, in RFC 3339 form.
+    pub deleted: String,
+}
+
+impl Item {
+    /// Returns the service name the item is stored under.
+    pub fn name(&self) -> String {
+        format!("{}{}/{}", PREFIX, self.deleted, self.service)
+    }
+
+    /// Parses a service name in the trash namespace.
+    pub fn parse(name: &str) -> Option<Self> {
+        let (deleted, service) = name.strip_prefix(PREFIX)?.split_once('/')?;
+        (!deleted.is_empty() && !service.is_empty()).then(|| Self {
+            service: service.to_string(),
+            deleted: deleted.to_string(),
+        })
+    }
+}
+
+/// Returns whether `service` lies in the trash namespace.
+pub fn contains(service: &str) -> bool {
+    service.starts_with(PREFIX)
+}
+
+/// Lists the deleted secrets of `account`, most recently deleted first.
+///
+/// The history, `@previous`, and `@oauth` items kept next to a trash item
+/// are left out.
+pub fn list(backend: &dyn Backend, account: &str) -> Result<Vec<Item>> {
+    let names: BTreeSet<String> = backend
+        .services(account)?
+        .into_iter()
+        .filter(|service| contains(service))
+        .collect();
+    let mut items: Vec<Item> = names
+        .iter()
+        .filter(|name| {
+            name.rsplit_once('@').is_none_or(|(base, version)| {
+                !names.contains(base)
+                    || !(matches!(version, "previous" | "oauth")
+                        || version.parse::<usize>().is_ok())
+            })
+        })
+        .filter_map(|name| Item::parse(name))
+        .collect();
+    items.sort_by(|a, b| {
+        b.deleted
+            .cmp(&a.deleted)
+            .then_with(|| a.service.cmp(&b.service))
+    });
+    Ok(items)
+}
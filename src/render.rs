@@ -0,0 +1,95 @@
+//! Styled terminal output.
+//!
+//! Color is only used on a stream that is a terminal, unless `--color
+//! always` asks for it. `NO_COLOR` turns it off in the default `auto` mode.
+//! Every helper returns the text unchanged when color is off, so output
+//! piped to a file or another program stays plain.
+
+use std::env;
+use std::fmt::Display;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use clap::ValueEnum;
+
+static STDOUT: AtomicBool = AtomicBool::new(false);
+static STDERR: AtomicBool = AtomicBool::new(false);
+
+const BOLD: &str = "1";
+const DIM: &str = "2";
+const RED: &str = "31";
+const GREEN: &str = "32";
+const YELLOW: &str = "33";
+const BOLD_YELLOW: &str = "1;33";
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum ColorWhen {
+    /// Color terminals unless NO_COLOR is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Decides once, before anything is printed, whether each stream gets
+/// color.
+pub fn init(when: ColorWhen) {
+    let enabled = |terminal: bool| match when {
+        ColorWhen::Always => true,
+        ColorWhen::Never => false,
+        ColorWhen::Auto => {
+            terminal
+                && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                && env::var_os("TERM").is_none_or(|term| term != "dumb")
+        }
+    };
+    STDOUT.store(enabled(io::stdout().is_terminal()), Ordering::Relaxed);
+    STDERR.store(enabled(io::stderr().is_terminal()), Ordering::Relaxed);
+}
+
+fn paint(stream: &AtomicBool, code: &str, text: impl Display) -> String {
+    match stream.load(Ordering::Relaxed) {
+        true => format!("\x1b[{}m{}\x1b[0m", code, text),
+        false => text.to_string(),
+    }
+}
+
+/// A service name printed to stdout.
+pub fn service(name: impl Display) -> String {
+    paint(&STDOUT, BOLD, name)
+}
+
+/// A table header or section heading printed to stdout.
+pub fn heading(text: impl Display) -> String {
+    paint(&STDOUT, BOLD, text)
+}
+
+/// Text on stdout for something that is fine, such as `ok`.
+pub fn good(text: impl Display) -> String {
+    paint(&STDOUT, GREEN, text)
+}
+
+/// Text on stdout for something that needs attention soon.
+pub fn caution(text: impl Display) -> String {
+    paint(&STDOUT, YELLOW, text)
+}
+
+/// Text on stdout for something that is wrong, such as `missing`.
+pub fn bad(text: impl Display) -> String {
+    paint(&STDOUT, RED, text)
+}
+
+/// Secondary text on stdout, such as tree branches.
+pub fn faint(text: impl Display) -> String {
+    paint(&STDOUT, DIM, text)
+}
+
+/// `hint: <message>`, for printing to stderr.
+pub fn hint(message: impl Display) -> String {
+    format!("{} {}", paint(&STDERR, DIM, "hint:"), message)
+}
+
+/// Prints `warning: <message>` to stderr.
+pub fn warning(message: impl Display) {
+    eprintln!("{} {}", paint(&STDERR, BOLD_YELLOW, "warning:"), message);
+}
@@ -0,0 +1,532 @@
+//! Terminal interfaces: the `keychainctl tui` browser, and the picker
+//! commands open when a service name is left out.
+//!
+//! The browser keeps values masked until revealed, and only reads them from
+//! the keychain when revealed or copied, so browsing never triggers keychain
+//! prompts.
+
+use std::io::{self, IsTerminal};
+
+use anyhow::{Result, anyhow};
+use keychainctl_core::backend::Backend;
+use keychainctl_core::{generate, registry};
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame, Terminal};
+
+use crate::{
+    DEFAULT_CLEAR_AFTER, Globals, authorize, copy_to_clipboard, open_backend, rotate_secret,
+    schedule_clipboard_clear, store_secret, trash_secret,
+};
+
+const MASK: &str = "••••••••";
+const HELP: &str = "/ search  enter reveal  c copy  e edit  r rotate  d delete  q quit";
+
+enum Mode {
+    Browse,
+    Search,
+    /// Typing a new value, shown masked.
+    Edit(String),
+    ConfirmDelete,
+    ConfirmRotate,
+}
+
+struct App<'a> {
+    globals: &'a Globals,
+    backend: Box<dyn Backend>,
+    account: String,
+    prefix: String,
+    /// Tracked services, with the profile prefix.
+    services: Vec<String>,
+    filter: String,
+    /// Indexes into `services` of the ones matching `filter`.
+    visible: Vec<usize>,
+    list: ListState,
+    /// The value of the selected service, once revealed.
+    revealed: Option<String>,
+    mode: Mode,
+    status: String,
+}
+
+pub(crate) fn run(account: String, globals: &Globals) -> Result<()> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(anyhow!("tui needs an interactive terminal"));
+    }
+    let prefix = globals.profile_prefix();
+    let mut app = App {
+        globals,
+        backend: open_backend(globals, false)?,
+        services: registry::list_prefix(&account, &prefix)?,
+        account,
+        prefix,
+        filter: String::new(),
+        visible: Vec::new(),
+        list: ListState::default(),
+        revealed: None,
+        mode: Mode::Browse,
+        status: HELP.to_string(),
+    };
+    app.refilter();
+
+    let mut terminal = ratatui::try_init()?;
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                return Ok(());
+            }
+            let rotated = matches!(self.mode, Mode::ConfirmRotate);
+            if !self.handle(key) {
+                return Ok(());
+            }
+            // A post-rotate hook may have written over the screen.
+            if rotated {
+                terminal.clear()?;
+            }
+        }
+    }
+
+    /// Applies a key press, returning false to quit.
+    fn handle(&mut self, key: KeyEvent) -> bool {
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => return self.browse(key.code),
+            Mode::Search => match key.code {
+                KeyCode::Enter => {}
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.refilter();
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.refilter();
+                    self.mode = Mode::Search;
+                }
+                KeyCode::Char(character) => {
+                    self.filter.push(character);
+                    self.refilter();
+                    self.mode = Mode::Search;
+                }
+                _ => self.mode = Mode::Search,
+            },
+            Mode::Edit(mut value) => match key.code {
+                KeyCode::Enter => self.finish(|app, service| app.edit(service, &value)),
+                KeyCode::Esc => self.status = "Edit cancelled.".to_string(),
+                KeyCode::Backspace => {
+                    value.pop();
+                    self.mode = Mode::Edit(value);
+                }
+                KeyCode::Char(character) => {
+                    value.push(character);
+                    self.mode = Mode::Edit(value);
+                }
+                _ => self.mode = Mode::Edit(value),
+            },
+            Mode::ConfirmDelete => match key.code {
+                KeyCode::Char('y' | 'Y') => self.finish(Self::delete),
+                _ => self.status = "Delete cancelled.".to_string(),
+            },
+            Mode::ConfirmRotate => match key.code {
+                KeyCode::Char('y' | 'Y') => self.finish(Self::rotate),
+                _ => self.status = "Rotate cancelled.".to_string(),
+            },
+        }
+        true
+    }
+
+    fn browse(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Esc if self.filter.is_empty() => return false,
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.refilter();
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.list.selected().map(|i| i + 1)),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.select(self.list.selected().map(|i| i.saturating_sub(1)))
+            }
+            KeyCode::Home | KeyCode::Char('g') => self.select(Some(0)),
+            KeyCode::End | KeyCode::Char('G') => self.select(self.visible.len().checked_sub(1)),
+            KeyCode::Char('/') => {
+                self.mode = Mode::Search;
+                self.status = "Type to filter; enter keeps the filter, esc clears it.".to_string();
+            }
+            _ if self.selected().is_none() => {}
+            KeyCode::Enter | KeyCode::Char(' ') if self.revealed.is_some() => self.revealed = None,
+            KeyCode::Enter | KeyCode::Char(' ') => self.finish(Self::reveal),
+            KeyCode::Char('c') => self.finish(Self::copy),
+            KeyCode::Char('e') => {
+                self.mode = Mode::Edit(String::new());
+                self.status = "New value (hidden); enter saves, esc cancels.".to_string();
+            }
+            KeyCode::Char('d') => {
+                self.mode = Mode::ConfirmDelete;
+                self.status = format!(
+                    "Delete `{}`? [y/N]",
+                    self.display(self.selected().unwrap_or_default())
+                );
+            }
+            KeyCode::Char('r') => {
+                self.mode = Mode::ConfirmRotate;
+                self.status = format!(
+                    "Replace `{}` with a random value? [y/N]",
+                    self.display(self.selected().unwrap_or_default())
+                );
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Runs `action` on the selected service, showing its outcome or error
+    /// in the status line.
+    fn finish(&mut self, action: impl FnOnce(&mut Self, &str) -> Result<String>) {
+        let Some(service) = self.selected().map(str::to_string) else {
+            return;
+        };
+        self.status = match action(self, &service) {
+            Ok(message) => message,
+            Err(error) => format!("Error: {:#}", error),
+        };
+    }
+
+    fn reveal(&mut self, service: &str) -> Result<String> {
+        authorize(self.globals, [service], "read")?;
+        self.revealed = Some(self.backend.get(&self.account, service)?);
+        Ok(HELP.to_string())
+    }
+
+    fn copy(&mut self, service: &str) -> Result<String> {
+        authorize(self.globals, [service], "read")?;
+        let value = self.backend.get(&self.account, service)?;
+        let after = self
+            .globals
+            .config
+            .clipboard_timeout()?
+            .unwrap_or(DEFAULT_CLEAR_AFTER);
+        copy_to_clipboard(&value)?;
+        if after > 0 {
+            schedule_clipboard_clear(&value, after)?;
+        }
+        Ok(format!(
+            "Copied `{}`; the clipboard clears in {}s.",
+            self.display(service),
+            after
+        ))
+    }
+
+    fn edit(&mut self, service: &str, value: &str) -> Result<String> {
+        if value.is_empty() {
+            return Err(anyhow!("refusing to store an empty secret"));
+        }
+        store_secret(
+            &*self.backend,
+            &self.account,
+            service,
+            value.as_bytes(),
+            self.globals,
+        )?;
+        self.revealed = None;
+        Ok(format!("Saved `{}`.", self.display(service)))
+    }
+
+    fn rotate(&mut self, service: &str) -> Result<String> {
+        let secret = generate::password(32, generate::Charset::Alnum)?;
+        rotate_secret(
+            &*self.backend,
+            &self.account,
+            service,
+            &secret,
+            false,
+            self.globals,
+        )?;
+        self.revealed = None;
+        Ok(format!(
+            "Rotated `{}`; the old value is `{}@previous`.",
+            self.display(service),
+            self.display(service)
+        ))
+    }
+
+    fn delete(&mut self, service: &str) -> Result<String> {
+        authorize(self.globals, [service], "delete")?;
+        trash_secret(&*self.backend, &self.account, service, self.globals)?;
+        let position = self.list.selected();
+        self.services.retain(|existing| existing != service);
+        self.refilter();
+        self.select(position);
+        Ok(format!("Moved `{}` to the trash.", self.display(service)))
+    }
+
+    /// Recomputes the services matching the filter, case-insensitively.
+    fn refilter(&mut self) {
+        let filter = self.filter.to_lowercase();
+        let selected = self.selected().map(str::to_string);
+        self.visible = (0..self.services.len())
+            .filter(|&index| {
+                self.display(&self.services[index])
+                    .to_lowercase()
+                    .contains(&filter)
+            })
+            .collect();
+        let position = selected
+            .and_then(|selected| {
+                self.visible
+                    .iter()
+                    .position(|&index| self.services[index] == selected)
+            })
+            .or(Some(0));
+        self.select(position);
+    }
+
+    fn select(&mut self, position: Option<usize>) {
+        let position = position
+            .filter(|_| !self.visible.is_empty())
+            .map(|position| position.min(self.visible.len() - 1));
+        if position != self.list.selected() {
+            self.revealed = None;
+        }
+        self.list.select(position);
+    }
+
+    fn selected(&self) -> Option<&str> {
+        let index = *self.visible.get(self.list.selected()?)?;
+        self.services.get(index).map(String::as_str)
+    }
+
+    /// Strips the active profile's prefix from `service`.
+    fn display<'s>(&self, service: &'s str) -> &'s str {
+        service.strip_prefix(&self.prefix).unwrap_or(service)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [search, body, status] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [list, preview] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(body);
+
+        let cursor = if matches!(self.mode, Mode::Search) {
+            "_"
+        } else {
+            ""
+        };
+        frame.render_widget(
+            Paragraph::new(format!("/{}{}", self.filter, cursor)).block(Block::bordered().title(
+                format!(
+                    " keychainctl — {} ({} of {}) ",
+                    self.account,
+                    self.visible.len(),
+                    self.services.len()
+                ),
+            )),
+            search,
+        );
+
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|&index| ListItem::new(self.display(&self.services[index]).to_string()))
+            .collect();
+        let empty = items.is_empty();
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::bordered().title(" Secrets "))
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+                .highlight_symbol("> "),
+            list,
+            &mut self.list,
+        );
+
+        let lines = match self.selected() {
+            None if empty && self.services.is_empty() => {
+                vec![Line::from(
+                    "No secrets are tracked. Add one with `keychainctl set`.",
+                )]
+            }
+            None => vec![Line::from("No secrets match the filter.")],
+            Some(service) => self.preview(service),
+        };
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(Block::bordered().title(" Preview "))
+                .wrap(Wrap { trim: false }),
+            preview,
+        );
+
+        let status_line = match &self.mode {
+            Mode::Edit(value) => format!("{} {}", self.status, "*".repeat(value.chars().count())),
+            _ => self.status.clone(),
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+    }
+
+    fn preview(&self, service: &str) -> Vec<Line<'static>> {
+        let value = self.revealed.clone().unwrap_or_else(|| MASK.to_string());
+        let mut lines = vec![
+            Line::from(format!("Service  {}", self.display(service))),
+            Line::from(format!("Account  {}", self.account)),
+            Line::from(format!("Value    {}", value)),
+        ];
+        if let Ok(Some(entry)) = registry::entry(&self.account, service) {
+            if let Some(expires) = entry.expires {
+                lines.push(Line::from(format!("Expires  {}", expires)));
+            }
+            if !entry.tags.is_empty() {
+                let tags: Vec<&str> = entry.tags.iter().map(String::as_str).collect();
+                lines.push(Line::from(format!("Tags     {}", tags.join(", "))));
+            }
+            if let Some(notes) = entry.notes {
+                lines.push(Line::from(format!("Notes    {}", notes)));
+            }
+        }
+        lines
+    }
+}
+
+/// Lets the user pick one of `items` by typing a fuzzy filter, returning
+/// `None` if they cancel. The picker draws on stderr, so stdout stays free
+/// for the command's output even when it is piped.
+pub(crate) fn pick(prompt: &str, items: &[String]) -> Result<Option<String>> {
+    terminal::enable_raw_mode()?;
+    let result = execute!(io::stderr(), EnterAlternateScreen)
+        .map_err(anyhow::Error::from)
+        .and_then(|()| {
+            let mut terminal = Terminal::new(CrosstermBackend::new(io::stderr()))?;
+            pick_loop(&mut terminal, prompt, items)
+        });
+    let restored = execute!(io::stderr(), LeaveAlternateScreen);
+    terminal::disable_raw_mode()?;
+    restored?;
+    result
+}
+
+fn pick_loop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stderr>>,
+    prompt: &str,
+    items: &[String],
+) -> Result<Option<String>> {
+    let mut query = String::new();
+    let mut matches = fuzzy_matches(items, &query);
+    let mut list = ListState::default().with_selected(Some(0));
+    loop {
+        terminal.draw(|frame| {
+            let [input, results] =
+                Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(frame.area());
+            frame.render_widget(
+                Paragraph::new(format!(
+                    "{}> {}  ({}/{})",
+                    prompt,
+                    query,
+                    matches.len(),
+                    items.len()
+                )),
+                input,
+            );
+            let entries: Vec<ListItem> = matches
+                .iter()
+                .map(|item| ListItem::new(item.as_str()))
+                .collect();
+            frame.render_stateful_widget(
+                List::new(entries)
+                    .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+                    .highlight_symbol("> "),
+                results,
+                &mut list,
+            );
+        })?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Char('c' | 'd') if control => return Ok(None),
+            KeyCode::Enter => {
+                return Ok(list
+                    .selected()
+                    .and_then(|index| matches.get(index).cloned()));
+            }
+            KeyCode::Up => list.select_previous(),
+            KeyCode::Char('p') if control => list.select_previous(),
+            KeyCode::Down | KeyCode::Tab => list.select_next(),
+            KeyCode::Char('n') if control => list.select_next(),
+            KeyCode::Backspace => {
+                query.pop();
+                matches = fuzzy_matches(items, &query);
+                list.select(Some(0));
+            }
+            KeyCode::Char(character) if !control => {
+                query.push(character);
+                matches = fuzzy_matches(items, &query);
+                list.select(Some(0));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the items containing the characters of `query` in order, best
+/// matches first.
+fn fuzzy_matches(items: &[String], query: &str) -> Vec<String> {
+    let mut scored: Vec<(i64, &String)> = items
+        .iter()
+        .filter_map(|item| Some((fuzzy_score(item, query)?, item)))
+        .collect();
+    scored.sort_by(|left, right| right.0.cmp(&left.0).then_with(|| left.1.cmp(right.1)));
+    scored.into_iter().map(|(_, item)| item.clone()).collect()
+}
+
+/// Scores `candidate` against `query`, case-insensitively. Runs of
+/// consecutive characters and matches at the start of a word (after `/`,
+/// `_`, `-`, or `.`) score higher, and gaps between matches cost a point
+/// per skipped character.
+fn fuzzy_score(candidate: &str, query: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.to_lowercase().chars() {
+        let offset = candidate[next..]
+            .iter()
+            .position(|&character| character == wanted)?;
+        let position = next + offset;
+        if previous.is_some_and(|previous| previous + 1 == position) {
+            score += 5;
+        } else if position == 0 || "/_-.".contains(candidate[position - 1]) {
+            score += 3;
+        }
+        if previous.is_some() {
+            score -= offset as i64;
+        }
+        previous = Some(position);
+        next = position + 1;
+    }
+    Some(score)
+}